@@ -0,0 +1,113 @@
+use super::dma::DmaHandle;
+use super::HalStatus;
+
+pub const ADC_RESOLUTION_12B: u32 = 0x0000_0000;
+pub const ADC_RESOLUTION_10B: u32 = 0x0100_0000;
+pub const ADC_RESOLUTION_8B: u32 = 0x0200_0000;
+pub const ADC_RESOLUTION_6B: u32 = 0x0300_0000;
+
+pub const ADC_DATAALIGN_RIGHT: u32 = 0x0000_0000;
+
+pub const ADC_EOC_SEQ_CONV: u32 = 0x0000_0000;
+pub const ADC_EOC_SINGLE_CONV: u32 = 0x0000_0001;
+
+pub const ADC_CHANNEL_TEMPSENSOR: u32 = 0x0000_0010;
+pub const ADC_CHANNEL_VREFINT: u32 = 0x0000_0011;
+pub const ADC_CHANNEL_VBAT: u32 = 0x0000_0012;
+
+pub const ADC_SAMPLETIME_3CYCLES: u32 = 0x0000_0000;
+pub const ADC_SAMPLETIME_15CYCLES: u32 = 0x0000_0001;
+pub const ADC_SAMPLETIME_28CYCLES: u32 = 0x0000_0002;
+pub const ADC_SAMPLETIME_56CYCLES: u32 = 0x0000_0003;
+pub const ADC_SAMPLETIME_84CYCLES: u32 = 0x0000_0004;
+pub const ADC_SAMPLETIME_112CYCLES: u32 = 0x0000_0005;
+pub const ADC_SAMPLETIME_144CYCLES: u32 = 0x0000_0006;
+pub const ADC_SAMPLETIME_480CYCLES: u32 = 0x0000_0007;
+
+pub const ADC_EXTERNALTRIGCONV_T1_CC1: u32 = 0x0000_0000;
+pub const ADC_EXTERNALTRIGCONV_T1_CC2: u32 = 0x0100_0000;
+pub const ADC_EXTERNALTRIGCONV_T1_CC3: u32 = 0x0200_0000;
+pub const ADC_EXTERNALTRIGCONV_T2_CC2: u32 = 0x0300_0000;
+pub const ADC_EXTERNALTRIGCONV_T2_CC3: u32 = 0x0400_0000;
+pub const ADC_EXTERNALTRIGCONV_T2_CC4: u32 = 0x0500_0000;
+pub const ADC_EXTERNALTRIGCONV_T2_TRGO: u32 = 0x0600_0000;
+pub const ADC_EXTERNALTRIGCONV_T3_CC1: u32 = 0x0700_0000;
+pub const ADC_EXTERNALTRIGCONV_T3_TRGO: u32 = 0x0800_0000;
+pub const ADC_EXTERNALTRIGCONV_T4_CC4: u32 = 0x0900_0000;
+pub const ADC_EXTERNALTRIGCONV_T5_CC1: u32 = 0x0A00_0000;
+pub const ADC_EXTERNALTRIGCONV_T5_CC2: u32 = 0x0B00_0000;
+pub const ADC_EXTERNALTRIGCONV_T5_CC3: u32 = 0x0C00_0000;
+pub const ADC_EXTERNALTRIGCONV_T8_CC1: u32 = 0x0D00_0000;
+pub const ADC_EXTERNALTRIGCONV_T8_TRGO: u32 = 0x0E00_0000;
+pub const ADC_EXTERNALTRIGCONV_EXT_IT11: u32 = 0x0F00_0000;
+pub const ADC_SOFTWARE_START: u32 = 0x0F00_0001;
+
+pub const ADC_EXTERNALTRIGCONVEDGE_NONE: u32 = 0x0000_0000;
+pub const ADC_EXTERNALTRIGCONVEDGE_RISING: u32 = 0x1000_0000;
+
+pub const HAL_ADC_ERROR_NONE: u32 = 0x0000_0000;
+pub const HAL_ADC_ERROR_INTERNAL: u32 = 0x0000_0001;
+pub const HAL_ADC_ERROR_OVR: u32 = 0x0000_0002;
+pub const HAL_ADC_ERROR_DMA: u32 = 0x0000_0004;
+
+/// The `FunctionalState` of the HAL.
+pub const DISABLE: u32 = 0;
+pub const ENABLE: u32 = 1;
+
+#[repr(C)]
+pub struct AdcInit
+{
+    pub clock_prescaler: u32,
+    pub resolution: u32,
+    pub data_align: u32,
+    pub scan_conv_mode: u32,
+    pub eoc_selection: u32,
+    pub continuous_conv_mode: u32,
+    pub nbr_of_conversion: u32,
+    pub discontinuous_conv_mode: u32,
+    pub nbr_of_disc_conversion: u32,
+    pub external_trig_conv: u32,
+    pub external_trig_conv_edge: u32,
+    pub dma_continuous_requests: u32,
+}
+
+#[repr(C)]
+pub struct AdcChannelConf
+{
+    pub channel: u32,
+    pub rank: u32,
+    pub sampling_time: u32,
+    pub offset: u32,
+}
+
+/// The head of `ADC_HandleTypeDef`, only ever used through the handles defined by the HAL.
+#[repr(C)]
+pub struct Hadc
+{
+    pub instance: u32,
+    pub init: AdcInit,
+    pub nbr_of_current_conversion_rank: u32,
+    pub dma_handle: *mut DmaHandle,
+}
+
+extern "C" {
+    #[cfg(feature = "adc1")]
+    pub static mut hadc1: Hadc;
+    #[cfg(feature = "adc2")]
+    pub static mut hadc2: Hadc;
+    #[cfg(feature = "adc3")]
+    pub static mut hadc3: Hadc;
+}
+
+extern "C" {
+    pub fn HAL_ADC_Init(hadc: &Hadc) -> HalStatus;
+    pub fn HAL_ADC_DeInit(hadc: &Hadc) -> HalStatus;
+    pub fn HAL_ADC_ConfigChannel(hadc: &Hadc, sConfig: &AdcChannelConf) -> HalStatus;
+    pub fn HAL_ADC_Start(hadc: &Hadc) -> HalStatus;
+    pub fn HAL_ADC_Stop(hadc: &Hadc) -> HalStatus;
+    pub fn HAL_ADC_PollForConversion(hadc: &Hadc, Timeout: u32) -> HalStatus;
+    pub fn HAL_ADC_GetValue(hadc: &Hadc) -> u32;
+    pub fn HAL_ADC_Start_DMA(hadc: &Hadc, pData: *mut u32, Length: u32) -> HalStatus;
+    pub fn HAL_ADC_Stop_DMA(hadc: &Hadc) -> HalStatus;
+    pub fn HAL_ADC_GetError(hadc: &Hadc) -> u32;
+}
@@ -0,0 +1,26 @@
+#[repr(C)]
+pub struct DmaStream
+{
+    pub cr: u32,
+    pub ndtr: u32,
+    pub par: u32,
+    pub m0ar: u32,
+    pub m1ar: u32,
+    pub fcr: u32,
+}
+
+/// The head of `DMA_HandleTypeDef`, only ever used through the handles linked to the peripheral handles.
+#[repr(C)]
+pub struct DmaHandle
+{
+    pub instance: *mut DmaStream,
+}
+
+impl DmaHandle
+{
+    /// The count of data the stream has still to transfer.
+    pub fn remaining(&self) -> u32
+    {
+        unsafe { core::ptr::read_volatile(core::ptr::addr_of!((*self.instance).ndtr)) }
+    }
+}
@@ -1,21 +1,109 @@
 use super::HalStatus;
 
+#[repr(C)]
+#[derive(PartialEq, Eq)]
+pub enum I2cState
+{
+    Reset = 0x00,
+    Ready = 0x20,
+    BusyTx = 0x21,
+    BusyRx = 0x22,
+    Busy = 0x24,
+    Listen = 0x28,
+    BusyTxListen = 0x29,
+    BusyRxListen = 0x2A,
+    Abort = 0x60,
+    Timeout = 0xA0,
+    Error = 0xE0,
+}
+
+pub const I2C_FIRST_FRAME: u32 = 0x0000_0001;
+pub const I2C_FIRST_AND_NEXT_FRAME: u32 = 0x0000_0002;
+pub const I2C_NEXT_FRAME: u32 = 0x0000_0004;
+pub const I2C_FIRST_AND_LAST_FRAME: u32 = 0x0000_0008;
+pub const I2C_LAST_FRAME_NO_STOP: u32 = 0x0000_0010;
+pub const I2C_LAST_FRAME: u32 = 0x0000_0020;
+
+pub const I2C_ADDRESSINGMODE_7BIT: u32 = 0x0000_4000;
+pub const I2C_ADDRESSINGMODE_10BIT: u32 = 0x0000_C000;
+
+pub const I2C_DUTYCYCLE_2: u32 = 0x0000_0000;
+pub const I2C_DUTYCYCLE_16_9: u32 = 0x0000_4000;
+
+pub const I2C_DUALADDRESS_DISABLE: u32 = 0x0000_0000;
+pub const I2C_DUALADDRESS_ENABLE: u32 = 0x0000_0001;
+
+pub const I2C_GENERALCALL_DISABLE: u32 = 0x0000_0000;
+pub const I2C_GENERALCALL_ENABLE: u32 = 0x0000_0040;
+
+pub const I2C_NOSTRETCH_DISABLE: u32 = 0x0000_0000;
+pub const I2C_NOSTRETCH_ENABLE: u32 = 0x0000_0080;
+
+pub const I2C_MEMADD_SIZE_8BIT: u16 = 0x0001;
+pub const I2C_MEMADD_SIZE_16BIT: u16 = 0x0010;
+
+pub const HAL_I2C_ERROR_NONE: u32 = 0x0000_0000;
+pub const HAL_I2C_ERROR_BERR: u32 = 0x0000_0001;
+pub const HAL_I2C_ERROR_ARLO: u32 = 0x0000_0002;
+pub const HAL_I2C_ERROR_AF: u32 = 0x0000_0004;
+pub const HAL_I2C_ERROR_OVR: u32 = 0x0000_0008;
+pub const HAL_I2C_ERROR_DMA: u32 = 0x0000_0010;
+pub const HAL_I2C_ERROR_TIMEOUT: u32 = 0x0000_0020;
+
+pub const I2C_SR1_ARLO: u32 = 0x0000_0200;
+pub const I2C_SR1_TIMEOUT: u32 = 0x0000_4000;
+
+pub const I2C_CR1_SMBUS: u32 = 0x0000_0002;
+pub const I2C_CR1_SMBTYPE: u32 = 0x0000_0008;
+
+#[repr(C)]
+pub struct I2cRegisters
+{
+    pub cr1: u32,
+    pub cr2: u32,
+    pub oar1: u32,
+    pub oar2: u32,
+    pub dr: u32,
+    pub sr1: u32,
+    pub sr2: u32,
+    pub ccr: u32,
+    pub trise: u32,
+    pub fltr: u32,
+}
+
+#[repr(C)]
+pub struct I2cInit
+{
+    pub clock_speed: u32,
+    pub duty_cycle: u32,
+    pub own_address1: u32,
+    pub addressing_mode: u32,
+    pub dual_address_mode: u32,
+    pub own_address2: u32,
+    pub general_call_mode: u32,
+    pub no_stretch_mode: u32,
+}
+
+/// The head of `I2C_HandleTypeDef`, only ever used through the handles defined by the HAL.
 #[repr(C)]
 pub struct Hi2c
 {
     pub instance: u32,
+    pub init: I2cInit,
 }
 
 extern "C" {
     #[cfg(feature = "i2c1")]
-    pub static hi2c1: Hi2c;
+    pub static mut hi2c1: Hi2c;
     #[cfg(feature = "i2c2")]
-    pub static hi2c2: Hi2c;
+    pub static mut hi2c2: Hi2c;
     #[cfg(feature = "i2c3")]
-    pub static hi2c3: Hi2c;
+    pub static mut hi2c3: Hi2c;
 }
 
 extern "C" {
+    pub fn HAL_I2C_Init(hi2c: &Hi2c) -> HalStatus;
+    pub fn HAL_I2C_DeInit(hi2c: &Hi2c) -> HalStatus;
     pub fn HAL_I2C_Master_Transmit(hi2c: &Hi2c, DevAddr: u16, pData: *const u8, Size: u16, Timeout: u32) -> HalStatus;
     pub fn HAL_I2C_Master_Receive(hi2c: &Hi2c, DevAddr: u16, pData: *const u8, Size: u16, Timeout: u32) -> HalStatus;
     pub fn HAL_I2C_Slave_Transmit(hi2c: &Hi2c, pData: *const u8, Size: u16, Timeout: u32) -> HalStatus;
@@ -46,6 +134,8 @@ extern "C" {
     pub fn HAL_I2C_Master_Seq_Receive_DMA(hi2c: &Hi2c, DevAddr: u16, pData: *const u8, Size: u16, XferOptions: u32) -> HalStatus;
     pub fn HAL_I2C_Slave_Seq_Transmit_DMA(hi2c: &Hi2c, pData: *const u8, Size: u16, XferOptions: u32) -> HalStatus;
     pub fn HAL_I2C_Slave_Seq_Receive_DMA(hi2c: &Hi2c, pData: *const u8, Size: u16, XferOptions: u32) -> HalStatus;
+    pub fn HAL_I2C_GetState(hi2c: &Hi2c) -> I2cState;
+    pub fn HAL_I2C_GetError(hi2c: &Hi2c) -> u32;
     pub fn HAL_I2C_EV_IRQHandler(hi2c: &Hi2c);
     pub fn HAL_I2C_ER_IRQHandler(hi2c: &Hi2c);
     pub fn HAL_I2C_MasterTxCpltCallback(hi2c: &Hi2c);
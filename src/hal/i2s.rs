@@ -0,0 +1,94 @@
+use super::dma::DmaHandle;
+use super::HalStatus;
+
+pub const I2S_MODE_SLAVE_TX: u32 = 0x0000_0000;
+pub const I2S_MODE_SLAVE_RX: u32 = 0x0000_0100;
+pub const I2S_MODE_MASTER_TX: u32 = 0x0000_0200;
+pub const I2S_MODE_MASTER_RX: u32 = 0x0000_0300;
+
+pub const I2S_STANDARD_PHILIPS: u32 = 0x0000_0000;
+pub const I2S_STANDARD_MSB: u32 = 0x0000_0010;
+pub const I2S_STANDARD_LSB: u32 = 0x0000_0020;
+pub const I2S_STANDARD_PCM_SHORT: u32 = 0x0000_0030;
+pub const I2S_STANDARD_PCM_LONG: u32 = 0x0000_00B0;
+
+pub const I2S_DATAFORMAT_16B: u32 = 0x0000_0000;
+pub const I2S_DATAFORMAT_16B_EXTENDED: u32 = 0x0000_0001;
+pub const I2S_DATAFORMAT_24B: u32 = 0x0000_0003;
+pub const I2S_DATAFORMAT_32B: u32 = 0x0000_0005;
+
+pub const I2S_MCLKOUTPUT_ENABLE: u32 = 0x0000_0200;
+pub const I2S_MCLKOUTPUT_DISABLE: u32 = 0x0000_0000;
+
+pub const I2S_AUDIOFREQ_8K: u32 = 8000;
+pub const I2S_AUDIOFREQ_192K: u32 = 192000;
+
+pub const I2S_CPOL_LOW: u32 = 0x0000_0000;
+pub const I2S_CPOL_HIGH: u32 = 0x0000_0008;
+
+pub const I2S_CLOCK_PLL: u32 = 0x0000_0000;
+pub const I2S_CLOCK_EXTERNAL: u32 = 0x0000_0001;
+
+pub const I2S_FULLDUPLEXMODE_DISABLE: u32 = 0x0000_0000;
+pub const I2S_FULLDUPLEXMODE_ENABLE: u32 = 0x0000_0001;
+
+pub const HAL_I2S_ERROR_NONE: u32 = 0x0000_0000;
+pub const HAL_I2S_ERROR_TIMEOUT: u32 = 0x0000_0001;
+pub const HAL_I2S_ERROR_OVR: u32 = 0x0000_0002;
+pub const HAL_I2S_ERROR_UDR: u32 = 0x0000_0004;
+pub const HAL_I2S_ERROR_DMA: u32 = 0x0000_0008;
+pub const HAL_I2S_ERROR_PRESCALER: u32 = 0x0000_0010;
+
+pub const SPI_I2SCFGR_CHLEN: u32 = 0x0000_0001;
+pub const SPI_I2SPR_I2SDIV: u32 = 0x0000_00FF;
+pub const SPI_I2SPR_ODD: u32 = 0x0000_0100;
+pub const SPI_I2SPR_MCKOE: u32 = 0x0000_0200;
+
+#[repr(C)]
+pub struct I2sInit
+{
+    pub mode: u32,
+    pub standard: u32,
+    pub data_format: u32,
+    pub mclk_output: u32,
+    pub audio_freq: u32,
+    pub cpol: u32,
+    pub clock_source: u32,
+    pub full_duplex_mode: u32,
+}
+
+/// The head of `I2S_HandleTypeDef`, only ever used through the handles defined by the HAL.
+#[repr(C)]
+pub struct Hi2s
+{
+    pub instance: u32,
+    pub init: I2sInit,
+    pub tx_buff_ptr: *const u16,
+    pub tx_xfer_size: u16,
+    pub tx_xfer_count: u16,
+    pub rx_buff_ptr: *const u16,
+    pub rx_xfer_size: u16,
+    pub rx_xfer_count: u16,
+    pub irq_handler_isr: *const u8,
+    pub hdmatx: *mut DmaHandle,
+    pub hdmarx: *mut DmaHandle,
+}
+
+extern "C" {
+    #[cfg(feature = "i2s2")]
+    pub static mut hi2s2: Hi2s;
+    #[cfg(feature = "i2s3")]
+    pub static mut hi2s3: Hi2s;
+}
+
+extern "C" {
+    pub fn HAL_I2S_Init(hi2s: &Hi2s) -> HalStatus;
+    pub fn HAL_I2S_DeInit(hi2s: &Hi2s) -> HalStatus;
+    pub fn HAL_I2S_Transmit_DMA(hi2s: &Hi2s, pData: *const u16, Size: u16) -> HalStatus;
+    pub fn HAL_I2S_Receive_DMA(hi2s: &Hi2s, pData: *const u16, Size: u16) -> HalStatus;
+    pub fn HAL_I2SEx_TransmitReceive_DMA(hi2s: &Hi2s, pTxData: *const u16, pRxData: *const u16, Size: u16) -> HalStatus;
+    pub fn HAL_I2S_DMAPause(hi2s: &Hi2s) -> HalStatus;
+    pub fn HAL_I2S_DMAResume(hi2s: &Hi2s) -> HalStatus;
+    pub fn HAL_I2S_DMAStop(hi2s: &Hi2s) -> HalStatus;
+    pub fn HAL_I2S_GetError(hi2s: &Hi2s) -> u32;
+}
@@ -1,10 +1,39 @@
 use super::HalStatus;
 
+pub const GPIO_PIN_RESET: u32 = 0;
+pub const GPIO_PIN_SET: u32 = 1;
+
+pub const GPIO_MODE_INPUT: u32 = 0x0000_0000;
+pub const GPIO_MODE_OUTPUT_PP: u32 = 0x0000_0001;
+pub const GPIO_MODE_OUTPUT_OD: u32 = 0x0000_0011;
+pub const GPIO_MODE_AF_PP: u32 = 0x0000_0002;
+pub const GPIO_MODE_AF_OD: u32 = 0x0000_0012;
+
+pub const GPIO_NOPULL: u32 = 0x0000_0000;
+pub const GPIO_PULLUP: u32 = 0x0000_0001;
+pub const GPIO_PULLDOWN: u32 = 0x0000_0002;
+
+pub const GPIO_SPEED_FREQ_LOW: u32 = 0x0000_0000;
+pub const GPIO_SPEED_FREQ_MEDIUM: u32 = 0x0000_0001;
+pub const GPIO_SPEED_FREQ_HIGH: u32 = 0x0000_0002;
+pub const GPIO_SPEED_FREQ_VERY_HIGH: u32 = 0x0000_0003;
+
 #[repr(C)]
 pub struct GPIO;
 
+#[repr(C)]
+pub struct GpioInit
+{
+    pub pin: u32,
+    pub mode: u32,
+    pub pull: u32,
+    pub speed: u32,
+    pub alternate: u32,
+}
+
 #[allow(improper_ctypes)]
 extern "C" {
+    pub fn HAL_GPIO_Init(GPIOx: *mut GPIO, GPIO_Init: &GpioInit);
     pub fn HAL_GPIO_ReadPin(GPIOx: *mut GPIO, GPIO_Pin: u16) -> u32;
     pub fn HAL_GPIO_WritePin(GPIOx: *mut GPIO, GPIO_Pin: u16, PinState: u32);
     pub fn HAL_GPIO_TogglePin(GPIOx: *mut GPIO, GPIO_Pin: u16);
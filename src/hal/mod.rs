@@ -1,8 +1,13 @@
+pub mod adc;
+pub mod dma;
 pub mod flash;
 pub mod i2c;
+pub mod i2s;
 pub mod io;
 pub mod iwdg;
 pub mod spi;
+pub mod system;
+pub mod tim;
 pub mod uart;
 pub mod wwdg;
 
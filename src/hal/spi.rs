@@ -1,27 +1,133 @@
+use super::dma::DmaHandle;
 use super::HalStatus;
 
+#[repr(C)]
+#[derive(PartialEq, Eq)]
+pub enum SpiState
+{
+    Reset = 0x00,
+    Ready = 0x01,
+    Busy = 0x02,
+    BusyTx = 0x03,
+    BusyRx = 0x04,
+    BusyTxRx = 0x05,
+    Error = 0x06,
+    Abort = 0x07,
+}
+
+pub const SPI_MODE_SLAVE: u32 = 0x0000_0000;
+pub const SPI_MODE_MASTER: u32 = 0x0000_0104;
+
+pub const SPI_DIRECTION_2LINES: u32 = 0x0000_0000;
+
+pub const SPI_NSS_SOFT: u32 = 0x0000_0200;
+pub const SPI_NSS_HARD_INPUT: u32 = 0x0000_0000;
+pub const SPI_NSS_HARD_OUTPUT: u32 = 0x0004_0000;
+
+pub const HAL_SPI_ERROR_NONE: u32 = 0x0000_0000;
+pub const HAL_SPI_ERROR_MODF: u32 = 0x0000_0001;
+pub const HAL_SPI_ERROR_CRC: u32 = 0x0000_0002;
+pub const HAL_SPI_ERROR_OVR: u32 = 0x0000_0004;
+pub const HAL_SPI_ERROR_FRE: u32 = 0x0000_0008;
+pub const HAL_SPI_ERROR_DMA: u32 = 0x0000_0010;
+pub const HAL_SPI_ERROR_FLAG: u32 = 0x0000_0020;
+pub const HAL_SPI_ERROR_ABORT: u32 = 0x0000_0040;
+
+pub const SPI_POLARITY_LOW: u32 = 0x0000_0000;
+pub const SPI_POLARITY_HIGH: u32 = 0x0000_0002;
+
+pub const SPI_PHASE_1EDGE: u32 = 0x0000_0000;
+pub const SPI_PHASE_2EDGE: u32 = 0x0000_0001;
+
+pub const SPI_DATASIZE_8BIT: u32 = 0x0000_0000;
+pub const SPI_DATASIZE_16BIT: u32 = 0x0000_0800;
+
+pub const SPI_FIRSTBIT_MSB: u32 = 0x0000_0000;
+pub const SPI_FIRSTBIT_LSB: u32 = 0x0000_0080;
+
+pub const SPI_CRCCALCULATION_DISABLE: u32 = 0x0000_0000;
+pub const SPI_CRCCALCULATION_ENABLE: u32 = 0x0000_2000;
+
+pub const SPI_BAUDRATEPRESCALER_2: u32 = 0x0000_0000;
+pub const SPI_BAUDRATEPRESCALER_4: u32 = 0x0000_0008;
+pub const SPI_BAUDRATEPRESCALER_8: u32 = 0x0000_0010;
+pub const SPI_BAUDRATEPRESCALER_16: u32 = 0x0000_0018;
+pub const SPI_BAUDRATEPRESCALER_32: u32 = 0x0000_0020;
+pub const SPI_BAUDRATEPRESCALER_64: u32 = 0x0000_0028;
+pub const SPI_BAUDRATEPRESCALER_128: u32 = 0x0000_0030;
+pub const SPI_BAUDRATEPRESCALER_256: u32 = 0x0000_0038;
+
+pub const SPI_SR_RXNE: u32 = 0x0000_0001;
+pub const SPI_SR_TXE: u32 = 0x0000_0002;
+pub const SPI_SR_CRCERR: u32 = 0x0000_0010;
+pub const SPI_SR_OVR: u32 = 0x0000_0040;
+pub const SPI_SR_BSY: u32 = 0x0000_0080;
+
+#[repr(C)]
+pub struct SpiRegisters
+{
+    pub cr1: u32,
+    pub cr2: u32,
+    pub sr: u32,
+    pub dr: u32,
+    pub crcpr: u32,
+    pub rxcrcr: u32,
+    pub txcrcr: u32,
+    pub i2scfgr: u32,
+    pub i2spr: u32,
+}
+
+#[repr(C)]
+pub struct SpiInit
+{
+    pub mode: u32,
+    pub direction: u32,
+    pub data_size: u32,
+    pub clk_polarity: u32,
+    pub clk_phase: u32,
+    pub nss: u32,
+    pub baud_rate_prescaler: u32,
+    pub first_bit: u32,
+    pub ti_mode: u32,
+    pub crc_calculation: u32,
+    pub crc_polynomial: u32,
+}
+
+/// The head of `SPI_HandleTypeDef`, only ever used through the handles defined by the HAL.
 #[repr(C)]
 pub struct Hspi
 {
     pub instance: u32,
+    pub init: SpiInit,
+    pub tx_buff_ptr: *const u8,
+    pub tx_xfer_size: u16,
+    pub tx_xfer_count: u16,
+    pub rx_buff_ptr: *const u8,
+    pub rx_xfer_size: u16,
+    pub rx_xfer_count: u16,
+    pub rx_isr: *const u8,
+    pub tx_isr: *const u8,
+    pub hdmatx: *mut DmaHandle,
+    pub hdmarx: *mut DmaHandle,
 }
 
 extern "C" {
     #[cfg(feature = "spi1")]
-    pub static hspi1: Hspi;
+    pub static mut hspi1: Hspi;
     #[cfg(feature = "spi2")]
-    pub static hspi2: Hspi;
+    pub static mut hspi2: Hspi;
     #[cfg(feature = "spi3")]
-    pub static hspi3: Hspi;
+    pub static mut hspi3: Hspi;
     #[cfg(feature = "spi4")]
-    pub static hspi4: Hspi;
+    pub static mut hspi4: Hspi;
     #[cfg(feature = "spi5")]
-    pub static hspi5: Hspi;
+    pub static mut hspi5: Hspi;
     #[cfg(feature = "spi6")]
-    pub static hspi6: Hspi;
+    pub static mut hspi6: Hspi;
 }
 
 extern "C" {
+    pub fn HAL_SPI_Init(hspi: &Hspi) -> HalStatus;
     pub fn HAL_SPI_Transmit(hspi: &Hspi, pData: *const u8, Size: u16, Timeout: u32) -> HalStatus;
     pub fn HAL_SPI_Receive(hspi: &Hspi, pData: *const u8, Size: u16, Timeout: u32) -> HalStatus;
     pub fn HAL_SPI_TransmitReceive(hspi: &Hspi, pTxData: *const u8, pRxData: *const u8, Size: u16, Timeout: u32) -> HalStatus;
@@ -33,4 +139,6 @@ extern "C" {
     pub fn HAL_SPI_TransmitReceive_DMA(hspi: &Hspi, pTxData: *const u8, pRxData: *const u8, Size: u16) -> HalStatus;
     pub fn HAL_SPI_Abort(hspi: &Hspi) -> HalStatus;
     pub fn HAL_SPI_Abort_IT(hspi: &Hspi) -> HalStatus;
+    pub fn HAL_SPI_GetState(hspi: &Hspi) -> SpiState;
+    pub fn HAL_SPI_GetError(hspi: &Hspi) -> u32;
 }
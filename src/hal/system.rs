@@ -0,0 +1,20 @@
+extern "C" {
+    pub static SystemCoreClock: u32;
+}
+
+extern "C" {
+    pub fn HAL_GetTick() -> u32;
+    pub fn HAL_Delay(Delay: u32);
+}
+
+extern "C" {
+    pub fn HAL_RCC_GetHCLKFreq() -> u32;
+    pub fn HAL_RCC_GetPCLK1Freq() -> u32;
+    pub fn HAL_RCC_GetPCLK2Freq() -> u32;
+}
+
+pub const RCC_PERIPHCLK_I2S: u32 = 0x0000_0001;
+
+extern "C" {
+    pub fn HAL_RCCEx_GetPeriphCLKFreq(PeriphClk: u32) -> u32;
+}
@@ -0,0 +1,233 @@
+use super::dma::DmaHandle;
+use super::HalStatus;
+
+pub const TIM_COUNTERMODE_UP: u32 = 0x0000_0000;
+
+pub const TIM_CLOCKDIVISION_DIV1: u32 = 0x0000_0000;
+
+pub const TIM_AUTORELOAD_PRELOAD_DISABLE: u32 = 0x0000_0000;
+pub const TIM_AUTORELOAD_PRELOAD_ENABLE: u32 = 0x0000_0080;
+
+pub const TIM_CHANNEL_1: u32 = 0x0000_0000;
+pub const TIM_CHANNEL_2: u32 = 0x0000_0004;
+pub const TIM_CHANNEL_3: u32 = 0x0000_0008;
+pub const TIM_CHANNEL_4: u32 = 0x0000_000C;
+
+pub const TIM_CHANNEL_ALL: u32 = 0x0000_003C;
+
+pub const HAL_TIM_ACTIVE_CHANNEL_1: u32 = 0x01;
+pub const HAL_TIM_ACTIVE_CHANNEL_2: u32 = 0x02;
+pub const HAL_TIM_ACTIVE_CHANNEL_3: u32 = 0x04;
+pub const HAL_TIM_ACTIVE_CHANNEL_4: u32 = 0x08;
+
+pub const TIM_OCMODE_PWM1: u32 = 0x0000_0060;
+
+pub const TIM_OCPOLARITY_HIGH: u32 = 0x0000_0000;
+pub const TIM_OCPOLARITY_LOW: u32 = 0x0000_0002;
+pub const TIM_OCNPOLARITY_HIGH: u32 = 0x0000_0000;
+pub const TIM_OCNPOLARITY_LOW: u32 = 0x0000_0008;
+
+pub const TIM_OCFAST_DISABLE: u32 = 0x0000_0000;
+pub const TIM_OCIDLESTATE_RESET: u32 = 0x0000_0000;
+pub const TIM_OCNIDLESTATE_RESET: u32 = 0x0000_0000;
+
+pub const TIM_OSSR_ENABLE: u32 = 0x0000_0800;
+pub const TIM_OSSI_ENABLE: u32 = 0x0000_0400;
+pub const TIM_LOCKLEVEL_OFF: u32 = 0x0000_0000;
+pub const TIM_BREAK_DISABLE: u32 = 0x0000_0000;
+pub const TIM_BREAK_ENABLE: u32 = 0x0000_1000;
+pub const TIM_BREAKPOLARITY_LOW: u32 = 0x0000_0000;
+pub const TIM_BREAKPOLARITY_HIGH: u32 = 0x0000_2000;
+pub const TIM_AUTOMATICOUTPUT_DISABLE: u32 = 0x0000_0000;
+pub const TIM_AUTOMATICOUTPUT_ENABLE: u32 = 0x0000_4000;
+
+pub const TIM_BDTR_MOE: u32 = 0x0000_8000;
+
+pub const TIM_ICPOLARITY_RISING: u32 = 0x0000_0000;
+pub const TIM_ICPOLARITY_FALLING: u32 = 0x0000_0002;
+pub const TIM_ICPOLARITY_BOTHEDGE: u32 = 0x0000_000A;
+
+pub const TIM_ICSELECTION_DIRECTTI: u32 = 0x0000_0001;
+pub const TIM_ICSELECTION_INDIRECTTI: u32 = 0x0000_0002;
+
+pub const TIM_ICPSC_DIV1: u32 = 0x0000_0000;
+
+pub const TIM_ENCODERMODE_TI12: u32 = 0x0000_0003;
+
+pub const TIM_SLAVEMODE_RESET: u32 = 0x0000_0004;
+pub const TIM_TS_TI1FP1: u32 = 0x0000_0050;
+pub const TIM_TRIGGERPOLARITY_RISING: u32 = 0x0000_0000;
+pub const TIM_TRIGGERPRESCALER_DIV1: u32 = 0x0000_0000;
+
+pub const TIM_CR1_URS: u32 = 0x0000_0004;
+pub const TIM_CR1_OPM: u32 = 0x0000_0008;
+pub const TIM_CR1_DIR: u32 = 0x0000_0010;
+pub const TIM_CR2_MMS: u32 = 0x0000_0070;
+pub const TIM_TRGO_UPDATE: u32 = 0x0000_0020;
+pub const TIM_DIER_UIE: u32 = 0x0000_0001;
+pub const TIM_DIER_BIE: u32 = 0x0000_0080;
+pub const TIM_SR_UIF: u32 = 0x0000_0001;
+
+#[repr(C)]
+pub struct TimRegisters
+{
+    pub cr1: u32,
+    pub cr2: u32,
+    pub smcr: u32,
+    pub dier: u32,
+    pub sr: u32,
+    pub egr: u32,
+    pub ccmr1: u32,
+    pub ccmr2: u32,
+    pub ccer: u32,
+    pub cnt: u32,
+    pub psc: u32,
+    pub arr: u32,
+    pub rcr: u32,
+    pub ccr1: u32,
+    pub ccr2: u32,
+    pub ccr3: u32,
+    pub ccr4: u32,
+    pub bdtr: u32,
+    pub dcr: u32,
+    pub dmar: u32,
+    pub or: u32,
+}
+
+#[repr(C)]
+pub struct TimInit
+{
+    pub prescaler: u32,
+    pub counter_mode: u32,
+    pub period: u32,
+    pub clock_division: u32,
+    pub repetition_counter: u32,
+    pub auto_reload_preload: u32,
+}
+
+#[repr(C)]
+pub struct TimOcInit
+{
+    pub oc_mode: u32,
+    pub pulse: u32,
+    pub oc_polarity: u32,
+    pub ocn_polarity: u32,
+    pub oc_fast_mode: u32,
+    pub oc_idle_state: u32,
+    pub ocn_idle_state: u32,
+}
+
+#[repr(C)]
+pub struct TimBreakDeadTimeConfig
+{
+    pub off_state_run_mode: u32,
+    pub off_state_idle_mode: u32,
+    pub lock_level: u32,
+    pub dead_time: u32,
+    pub break_state: u32,
+    pub break_polarity: u32,
+    pub break_filter: u32,
+    pub automatic_output: u32,
+}
+
+#[repr(C)]
+pub struct TimIcInit
+{
+    pub ic_polarity: u32,
+    pub ic_selection: u32,
+    pub ic_prescaler: u32,
+    pub ic_filter: u32,
+}
+
+#[repr(C)]
+pub struct TimSlaveConfig
+{
+    pub slave_mode: u32,
+    pub input_trigger: u32,
+    pub trigger_polarity: u32,
+    pub trigger_prescaler: u32,
+    pub trigger_filter: u32,
+}
+
+#[repr(C)]
+pub struct TimEncoderInit
+{
+    pub encoder_mode: u32,
+    pub ic1_polarity: u32,
+    pub ic1_selection: u32,
+    pub ic1_prescaler: u32,
+    pub ic1_filter: u32,
+    pub ic2_polarity: u32,
+    pub ic2_selection: u32,
+    pub ic2_prescaler: u32,
+    pub ic2_filter: u32,
+}
+
+/// The head of `TIM_HandleTypeDef`, only ever used through the handles defined by the HAL.
+#[repr(C)]
+pub struct Htim
+{
+    pub instance: u32,
+    pub init: TimInit,
+    pub channel: u32,
+    pub hdma: [*mut DmaHandle; 7],
+}
+
+extern "C" {
+    #[cfg(feature = "tim1")]
+    pub static mut htim1: Htim;
+    #[cfg(feature = "tim2")]
+    pub static mut htim2: Htim;
+    #[cfg(feature = "tim3")]
+    pub static mut htim3: Htim;
+    #[cfg(feature = "tim4")]
+    pub static mut htim4: Htim;
+    #[cfg(feature = "tim5")]
+    pub static mut htim5: Htim;
+    #[cfg(feature = "tim6")]
+    pub static mut htim6: Htim;
+    #[cfg(feature = "tim7")]
+    pub static mut htim7: Htim;
+    #[cfg(feature = "tim8")]
+    pub static mut htim8: Htim;
+    #[cfg(feature = "tim9")]
+    pub static mut htim9: Htim;
+    #[cfg(feature = "tim10")]
+    pub static mut htim10: Htim;
+    #[cfg(feature = "tim11")]
+    pub static mut htim11: Htim;
+    #[cfg(feature = "tim12")]
+    pub static mut htim12: Htim;
+    #[cfg(feature = "tim13")]
+    pub static mut htim13: Htim;
+    #[cfg(feature = "tim14")]
+    pub static mut htim14: Htim;
+}
+
+extern "C" {
+    pub fn HAL_TIM_Base_Init(htim: &Htim) -> HalStatus;
+    pub fn HAL_TIM_Base_DeInit(htim: &Htim) -> HalStatus;
+    pub fn HAL_TIM_Base_Start(htim: &Htim) -> HalStatus;
+    pub fn HAL_TIM_Base_Stop(htim: &Htim) -> HalStatus;
+    pub fn HAL_TIM_Base_Start_IT(htim: &Htim) -> HalStatus;
+    pub fn HAL_TIM_Base_Stop_IT(htim: &Htim) -> HalStatus;
+    pub fn HAL_TIM_PWM_Init(htim: &Htim) -> HalStatus;
+    pub fn HAL_TIM_PWM_ConfigChannel(htim: &Htim, sConfig: &TimOcInit, Channel: u32) -> HalStatus;
+    pub fn HAL_TIM_PWM_Start(htim: &Htim, Channel: u32) -> HalStatus;
+    pub fn HAL_TIM_PWM_Stop(htim: &Htim, Channel: u32) -> HalStatus;
+    pub fn HAL_TIM_PWM_Start_DMA(htim: &Htim, Channel: u32, pData: *const u32, Length: u16) -> HalStatus;
+    pub fn HAL_TIM_PWM_Stop_DMA(htim: &Htim, Channel: u32) -> HalStatus;
+    pub fn HAL_TIM_IC_Init(htim: &Htim) -> HalStatus;
+    pub fn HAL_TIM_IC_ConfigChannel(htim: &Htim, sConfig: &TimIcInit, Channel: u32) -> HalStatus;
+    pub fn HAL_TIM_IC_Start_IT(htim: &Htim, Channel: u32) -> HalStatus;
+    pub fn HAL_TIM_IC_Stop_IT(htim: &Htim, Channel: u32) -> HalStatus;
+    pub fn HAL_TIM_IC_Start_DMA(htim: &Htim, Channel: u32, pData: *mut u32, Length: u16) -> HalStatus;
+    pub fn HAL_TIM_IC_Stop_DMA(htim: &Htim, Channel: u32) -> HalStatus;
+    pub fn HAL_TIM_SlaveConfigSynchro(htim: &Htim, sSlaveConfig: &TimSlaveConfig) -> HalStatus;
+    pub fn HAL_TIM_Encoder_Init(htim: &Htim, sConfig: &TimEncoderInit) -> HalStatus;
+    pub fn HAL_TIM_Encoder_Start(htim: &Htim, Channel: u32) -> HalStatus;
+    pub fn HAL_TIM_Encoder_Stop(htim: &Htim, Channel: u32) -> HalStatus;
+    pub fn HAL_TIMEx_PWMN_Start(htim: &Htim, Channel: u32) -> HalStatus;
+    pub fn HAL_TIMEx_PWMN_Stop(htim: &Htim, Channel: u32) -> HalStatus;
+    pub fn HAL_TIMEx_ConfigBreakDeadTime(htim: &Htim, sBreakDeadTimeConfig: &TimBreakDeadTimeConfig) -> HalStatus;
+}
@@ -14,8 +14,33 @@ pub enum State
     Error = 0xE0,
 }
 
+pub const UART_ERROR_NONE: u32 = 0x0000_0000;
+pub const UART_ERROR_PE: u32 = 0x0000_0001;
+pub const UART_ERROR_NE: u32 = 0x0000_0002;
+pub const UART_ERROR_FE: u32 = 0x0000_0004;
+pub const UART_ERROR_ORE: u32 = 0x0000_0008;
+pub const UART_ERROR_DMA: u32 = 0x0000_0010;
+
+pub const UART_LINBREAKDETECTLENGTH_10B: u32 = 0x0000_0000;
+pub const UART_LINBREAKDETECTLENGTH_11B: u32 = 0x0000_0020;
+
+#[repr(C)]
+pub struct UartHandle
+{
+    pub instance: u32,
+}
+
 #[repr(C)]
-pub struct UartHandle;
+pub struct UartRegisters
+{
+    pub sr: u32,
+    pub dr: u32,
+    pub brr: u32,
+    pub cr1: u32,
+    pub cr2: u32,
+    pub cr3: u32,
+    pub gtpr: u32,
+}
 
 #[allow(improper_ctypes)]
 extern "C" {
@@ -35,4 +60,7 @@ extern "C" {
     pub fn HAL_UARTEx_ReceiveToIdle_IT(huart: *mut UartHandle, pData: *const u8, Size: u16) -> HalStatus;
     pub fn HAL_UARTEx_ReceiveToIdle_DMA(huart: *mut UartHandle, pData: *const u8, Size: u16) -> HalStatus;
     pub fn HAL_UART_GetState(huart: *mut UartHandle) -> State;
+    pub fn HAL_UART_GetError(huart: *mut UartHandle) -> u32;
+    pub fn HAL_LIN_Init(huart: *mut UartHandle, BreakDetectLength: u32) -> HalStatus;
+    pub fn HAL_LIN_SendBreak(huart: *mut UartHandle) -> HalStatus;
 }
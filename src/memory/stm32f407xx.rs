@@ -14,6 +14,7 @@ pub const BKPSRAM_BB_BASE: u32 = 0x4248_0000;
 pub const FLASH_END: u32 = 0x081F_FFFF;
 pub const FLASH_OTP_BASE: u32 = 0x1FFF_7800;
 pub const FLASH_OTP_END: u32 = 0x1FFF_7A0F;
+pub const VREFINT_CAL_ADDR: u32 = 0x1FFF_7A2A;
 pub const CCMDATARAM_END: u32 = 0x1000_FFFF;
 
 pub const APB1PERIPH_BASE: u32 = PERIPH_BASE;
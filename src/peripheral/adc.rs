@@ -0,0 +1,516 @@
+use escw_mcu::common::Error;
+use escw_mcu::common::Result;
+
+use crate::hal::adc::*;
+
+/// The most conversions of the regular group.
+pub const ADC_SEQUENCE_SIZE: usize = 16;
+
+/// The VDDA in mV at which the factory has measured VREFINT.
+const VREFINT_CAL_VDDA: u32 = 3300;
+
+/// The ADCs, ADC1 alone measures VREFINT, the temperature sensor and VBAT.
+#[derive(Clone, Copy)]
+pub enum AdcIdentifies
+{
+    #[cfg(feature = "adc1")]
+    Adc1,
+    #[cfg(feature = "adc2")]
+    Adc2,
+    #[cfg(feature = "adc3")]
+    Adc3,
+}
+
+impl AdcIdentifies
+{
+    pub const fn count() -> usize
+    {
+        3
+    }
+}
+
+impl Into<*mut Hadc> for AdcIdentifies
+{
+    fn into(self) -> *mut Hadc
+    {
+        match self {
+            #[cfg(feature = "adc1")]
+            Self::Adc1 => core::ptr::addr_of_mut!(hadc1),
+            #[cfg(feature = "adc2")]
+            Self::Adc2 => core::ptr::addr_of_mut!(hadc2),
+            #[cfg(feature = "adc3")]
+            Self::Adc3 => core::ptr::addr_of_mut!(hadc3),
+        }
+    }
+}
+
+impl Into<&Hadc> for AdcIdentifies
+{
+    fn into(self) -> &'static Hadc
+    {
+        unsafe { &*Into::<*mut Hadc>::into(self) }
+    }
+}
+
+impl TryInto<AdcIdentifies> for &Hadc
+{
+    type Error = Error;
+
+    fn try_into(self) -> core::result::Result<AdcIdentifies, Self::Error>
+    {
+        match self.instance {
+            #[cfg(feature = "adc1")]
+            crate::memory::ADC1_BASE => Ok(AdcIdentifies::Adc1),
+            #[cfg(feature = "adc2")]
+            crate::memory::ADC2_BASE => Ok(AdcIdentifies::Adc2),
+            #[cfg(feature = "adc3")]
+            crate::memory::ADC3_BASE => Ok(AdcIdentifies::Adc3),
+            _ => Err(Error::Param),
+        }
+    }
+}
+
+/// The inputs of an ADC, the internal ones are only on ADC1.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum AdcChannel
+{
+    /// One of the 16 external inputs, from 0 to 15.
+    Input(u8),
+    TempSensor,
+    VrefInt,
+    VBat,
+}
+
+impl AdcChannel
+{
+    fn hal(&self) -> Result<u32>
+    {
+        match self {
+            Self::Input(input) if *input < 16 => Ok(*input as u32),
+            Self::Input(_) => Err(Error::Param),
+            Self::TempSensor => Ok(ADC_CHANNEL_TEMPSENSOR),
+            Self::VrefInt => Ok(ADC_CHANNEL_VREFINT),
+            Self::VBat => Ok(ADC_CHANNEL_VBAT),
+        }
+    }
+
+    fn is_internal(&self) -> bool
+    {
+        !matches!(self, Self::Input(_))
+    }
+}
+
+/// The ADC clock cycles a channel is sampled for, longer for sources of higher impedance.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum AdcSamplingTime
+{
+    Cycles3,
+    Cycles15,
+    Cycles28,
+    Cycles56,
+    Cycles84,
+    Cycles112,
+    Cycles144,
+    Cycles480,
+}
+
+impl Into<u32> for AdcSamplingTime
+{
+    fn into(self) -> u32
+    {
+        match self {
+            Self::Cycles3 => ADC_SAMPLETIME_3CYCLES,
+            Self::Cycles15 => ADC_SAMPLETIME_15CYCLES,
+            Self::Cycles28 => ADC_SAMPLETIME_28CYCLES,
+            Self::Cycles56 => ADC_SAMPLETIME_56CYCLES,
+            Self::Cycles84 => ADC_SAMPLETIME_84CYCLES,
+            Self::Cycles112 => ADC_SAMPLETIME_112CYCLES,
+            Self::Cycles144 => ADC_SAMPLETIME_144CYCLES,
+            Self::Cycles480 => ADC_SAMPLETIME_480CYCLES,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum AdcResolution
+{
+    Bits12,
+    Bits10,
+    Bits8,
+    Bits6,
+}
+
+impl Into<u32> for AdcResolution
+{
+    fn into(self) -> u32
+    {
+        match self {
+            Self::Bits12 => ADC_RESOLUTION_12B,
+            Self::Bits10 => ADC_RESOLUTION_10B,
+            Self::Bits8 => ADC_RESOLUTION_8B,
+            Self::Bits6 => ADC_RESOLUTION_6B,
+        }
+    }
+}
+
+/// What starts the conversions of the regular group, on its rising edge for the external ones.
+///
+/// A timer gives its TRGO once `Timer::enable_update_trigger` has been called, at each period.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum AdcTrigger
+{
+    /// The conversions follow each other as fast as the ADC goes.
+    Software,
+    Tim1Cc1,
+    Tim1Cc2,
+    Tim1Cc3,
+    Tim2Cc2,
+    Tim2Cc3,
+    Tim2Cc4,
+    Tim2Trgo,
+    Tim3Cc1,
+    Tim3Trgo,
+    Tim4Cc4,
+    Tim5Cc1,
+    Tim5Cc2,
+    Tim5Cc3,
+    Tim8Cc1,
+    Tim8Trgo,
+    Exti11,
+}
+
+impl Into<u32> for AdcTrigger
+{
+    fn into(self) -> u32
+    {
+        match self {
+            Self::Software => ADC_SOFTWARE_START,
+            Self::Tim1Cc1 => ADC_EXTERNALTRIGCONV_T1_CC1,
+            Self::Tim1Cc2 => ADC_EXTERNALTRIGCONV_T1_CC2,
+            Self::Tim1Cc3 => ADC_EXTERNALTRIGCONV_T1_CC3,
+            Self::Tim2Cc2 => ADC_EXTERNALTRIGCONV_T2_CC2,
+            Self::Tim2Cc3 => ADC_EXTERNALTRIGCONV_T2_CC3,
+            Self::Tim2Cc4 => ADC_EXTERNALTRIGCONV_T2_CC4,
+            Self::Tim2Trgo => ADC_EXTERNALTRIGCONV_T2_TRGO,
+            Self::Tim3Cc1 => ADC_EXTERNALTRIGCONV_T3_CC1,
+            Self::Tim3Trgo => ADC_EXTERNALTRIGCONV_T3_TRGO,
+            Self::Tim4Cc4 => ADC_EXTERNALTRIGCONV_T4_CC4,
+            Self::Tim5Cc1 => ADC_EXTERNALTRIGCONV_T5_CC1,
+            Self::Tim5Cc2 => ADC_EXTERNALTRIGCONV_T5_CC2,
+            Self::Tim5Cc3 => ADC_EXTERNALTRIGCONV_T5_CC3,
+            Self::Tim8Cc1 => ADC_EXTERNALTRIGCONV_T8_CC1,
+            Self::Tim8Trgo => ADC_EXTERNALTRIGCONV_T8_TRGO,
+            Self::Exti11 => ADC_EXTERNALTRIGCONV_EXT_IT11,
+        }
+    }
+}
+
+/// What happened to a circular DMA stream of conversions, the halves are the halves of the buffer.
+#[derive(Clone, Copy)]
+pub enum AdcEvent
+{
+    /// The first half has been filled, it can be read.
+    Half,
+    /// The second half has been filled, it can be read.
+    Completed,
+    Error(AdcError),
+}
+
+pub type AdcEventHandle = fn(AdcEvent);
+
+/// The error bits reported by the HAL when an error interrupt occurs.
+#[derive(Clone, Copy)]
+pub struct AdcError(u32);
+
+impl AdcError
+{
+    pub fn internal(&self) -> bool
+    {
+        self.0 & HAL_ADC_ERROR_INTERNAL != 0
+    }
+
+    /// A conversion has been lost before the DMA took it.
+    pub fn overrun(&self) -> bool
+    {
+        self.0 & HAL_ADC_ERROR_OVR != 0
+    }
+
+    pub fn dma(&self) -> bool
+    {
+        self.0 & HAL_ADC_ERROR_DMA != 0
+    }
+}
+
+impl From<u32> for AdcError
+{
+    fn from(value: u32) -> Self
+    {
+        AdcError(value)
+    }
+}
+
+impl Into<u32> for AdcError
+{
+    fn into(self) -> u32
+    {
+        self.0
+    }
+}
+
+/// An ADC converting its regular group, read blocking or streamed by circular DMA.
+///
+/// Each call sets the group again, so the channels of a blocking read and of a stream can differ. The
+/// clock prescaler is kept from the HAL initialization.
+pub struct Adc
+{
+    adc: AdcIdentifies,
+}
+
+impl Adc
+{
+    pub fn new(adc: AdcIdentifies) -> Self
+    {
+        Adc { adc }
+    }
+
+    pub fn with_event(&mut self, handle: AdcEventHandle)
+    {
+        event::EventCenter::set(self.adc, handle)
+    }
+
+    /// Initialize the ADC again with another resolution.
+    pub fn configure(&self, resolution: AdcResolution) -> Result<()>
+    {
+        unsafe {
+            let hadc: *mut Hadc = self.adc.into();
+
+            (*hadc).init.resolution = resolution.into();
+            (*hadc).init.data_align = ADC_DATAALIGN_RIGHT;
+
+            HAL_ADC_Init(&*hadc).into()
+        }
+    }
+
+    /// Convert one channel, waiting up to `timeout` ms.
+    pub fn read(&self, channel: AdcChannel, sampling_time: AdcSamplingTime, timeout: u32) -> Result<u16>
+    {
+        let mut value = [0];
+
+        self.scan(&[(channel, sampling_time)], &mut value, timeout)?;
+
+        Ok(value[0])
+    }
+
+    /// Convert each channel in turn into `values`, which has one value per channel, waiting up to
+    /// `timeout` ms for each one.
+    pub fn scan(&self, channels: &[(AdcChannel, AdcSamplingTime)], values: &mut [u16], timeout: u32) -> Result<()>
+    {
+        if values.len() != channels.len() {
+            return Err(Error::Param);
+        }
+
+        self.sequence(channels, AdcTrigger::Software, false)?;
+
+        let hadc: &Hadc = self.adc.into();
+
+        // Each start converts the next channel alone, which is read before the next one can overrun it.
+        unsafe {
+            for value in values.iter_mut() {
+                if let Err(error) = HAL_ADC_Start(hadc).ok().and_then(|_| HAL_ADC_PollForConversion(hadc, timeout).ok()) {
+                    HAL_ADC_Stop(hadc);
+                    return Err(error);
+                }
+
+                *value = HAL_ADC_GetValue(hadc) as u16;
+            }
+
+            HAL_ADC_Stop(hadc).into()
+        }
+    }
+
+    /// Convert the channels endlessly into `values`, one value per channel in turn, each scan started by
+    /// `trigger`, until `stop_with_dma`.
+    ///
+    /// The DMA stream of the ADC must be circular and move halfwords. `values` holds two halves of whole
+    /// scans, `AdcEvent::Half` and `AdcEvent::Completed` tell which half has just been filled.
+    pub fn start_with_dma(&self, channels: &[(AdcChannel, AdcSamplingTime)], trigger: AdcTrigger, values: &'static mut [u16]) -> Result<()>
+    {
+        if values.is_empty() || values.len() > u16::MAX as usize || !values.len().is_multiple_of(channels.len().max(1) * 2) {
+            return Err(Error::Param);
+        }
+
+        self.sequence(channels, trigger, true)?;
+
+        unsafe { HAL_ADC_Start_DMA(self.adc.into(), values.as_mut_ptr() as *mut u32, values.len() as u32).into() }
+    }
+
+    pub fn stop_with_dma(&self) -> Result<()>
+    {
+        unsafe { HAL_ADC_Stop_DMA(self.adc.into()).into() }
+    }
+
+    /// The error of the last conversions, also given by `AdcEvent::Error`.
+    pub fn error(&self) -> AdcError
+    {
+        AdcError::from(unsafe { HAL_ADC_GetError(self.adc.into()) })
+    }
+
+    /// Measure VDDA in mV from VREFINT and its factory calibration, on ADC1 alone, waiting up to
+    /// `timeout` ms.
+    pub fn vdda(&self, timeout: u32) -> Result<u32>
+    {
+        let raw = self.read(AdcChannel::VrefInt, AdcSamplingTime::Cycles480, timeout)? as u32;
+
+        if raw == 0 {
+            return Err(Error::Unknown);
+        }
+
+        // The calibration is taken at 12 bits.
+        let raw = raw << (12 - self.bits());
+        let calibration = unsafe { core::ptr::read_volatile(crate::memory::VREFINT_CAL_ADDR as *const u16) };
+
+        Ok(VREFINT_CAL_VDDA * calibration as u32 / raw)
+    }
+
+    /// Turn a value of this ADC into mV, for the VDDA given by `vdda`.
+    pub fn millivolts(&self, value: u16, vdda: u32) -> u32
+    {
+        (value as u64 * vdda as u64 / ((1 << self.bits()) - 1)) as u32
+    }
+
+    /// The bits of the values, from the resolution of the ADC.
+    fn bits(&self) -> u32
+    {
+        let hadc: &Hadc = self.adc.into();
+
+        match hadc.init.resolution {
+            ADC_RESOLUTION_10B => 10,
+            ADC_RESOLUTION_8B => 8,
+            ADC_RESOLUTION_6B => 6,
+            _ => 12,
+        }
+    }
+
+    /// Set the regular group to `channels`, converted one by one for the blocking reads, the whole group
+    /// at once for the DMA.
+    fn sequence(&self, channels: &[(AdcChannel, AdcSamplingTime)], trigger: AdcTrigger, dma: bool) -> Result<()>
+    {
+        let hadc: &Hadc = self.adc.into();
+
+        if channels.is_empty() || channels.len() > ADC_SEQUENCE_SIZE {
+            return Err(Error::Param);
+        }
+
+        if hadc.instance != crate::memory::ADC1_BASE && channels.iter().any(|(channel, _)| channel.is_internal()) {
+            return Err(Error::Param);
+        }
+
+        let enable = |enable: bool| match enable {
+            true => ENABLE,
+            false => DISABLE,
+        };
+
+        unsafe {
+            let hadc: *mut Hadc = self.adc.into();
+
+            (*hadc).init.scan_conv_mode = enable(channels.len() > 1);
+            (*hadc).init.eoc_selection = match dma {
+                true => ADC_EOC_SEQ_CONV,
+                false => ADC_EOC_SINGLE_CONV,
+            };
+            (*hadc).init.continuous_conv_mode = enable(dma && trigger == AdcTrigger::Software);
+            (*hadc).init.nbr_of_conversion = channels.len() as u32;
+            (*hadc).init.discontinuous_conv_mode = enable(!dma && channels.len() > 1);
+            (*hadc).init.nbr_of_disc_conversion = 1;
+            (*hadc).init.external_trig_conv = trigger.into();
+            (*hadc).init.external_trig_conv_edge = match trigger {
+                AdcTrigger::Software => ADC_EXTERNALTRIGCONVEDGE_NONE,
+                _ => ADC_EXTERNALTRIGCONVEDGE_RISING,
+            };
+            (*hadc).init.dma_continuous_requests = enable(dma);
+
+            HAL_ADC_Init(&*hadc).ok()?;
+
+            for (rank, (channel, sampling_time)) in channels.iter().enumerate() {
+                let config = AdcChannelConf {
+                    channel: channel.hal()?,
+                    rank: rank as u32 + 1,
+                    sampling_time: (*sampling_time).into(),
+                    offset: 0,
+                };
+
+                HAL_ADC_ConfigChannel(&*hadc, &config).ok()?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+mod event
+{
+    use crate::hal::adc::*;
+
+    use super::AdcEvent;
+    use super::AdcEventHandle;
+    use super::AdcIdentifies;
+
+    static mut EVENT_CENTER: EventCenter = EventCenter::new();
+
+    pub struct EventCenter
+    {
+        handle: [Option<AdcEventHandle>; AdcIdentifies::count()],
+    }
+
+    impl EventCenter
+    {
+        const fn new() -> Self
+        {
+            EventCenter {
+                handle: [None; AdcIdentifies::count()],
+            }
+        }
+
+        pub fn set(adc: AdcIdentifies, invoke: AdcEventHandle)
+        {
+            unsafe {
+                EVENT_CENTER.handle[adc as usize] = Some(invoke);
+            }
+        }
+
+        pub fn invoke(adc: AdcIdentifies, event: AdcEvent)
+        {
+            unsafe {
+                if let Some(invoke) = EVENT_CENTER.handle[adc as usize].as_ref() {
+                    invoke(event);
+                }
+            }
+        }
+    }
+
+    #[no_mangle]
+    pub extern "C" fn HAL_ADC_ConvHalfCpltCallback(hadc: &Hadc)
+    {
+        if let Some(adc) = hadc.try_into().ok() {
+            EventCenter::invoke(adc, AdcEvent::Half);
+        }
+    }
+
+    #[no_mangle]
+    pub extern "C" fn HAL_ADC_ConvCpltCallback(hadc: &Hadc)
+    {
+        if let Some(adc) = hadc.try_into().ok() {
+            EventCenter::invoke(adc, AdcEvent::Completed);
+        }
+    }
+
+    #[no_mangle]
+    pub extern "C" fn HAL_ADC_ErrorCallback(hadc: &Hadc)
+    {
+        if let Some(adc) = hadc.try_into().ok() {
+            let error = unsafe { HAL_ADC_GetError(hadc) };
+
+            EventCenter::invoke(adc, AdcEvent::Error(error.into()));
+        }
+    }
+}
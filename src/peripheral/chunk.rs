@@ -0,0 +1,83 @@
+use escw_mcu::common::Result;
+
+/// The most frames the HAL takes in one call, its sizes are 16 bits.
+pub const CHUNK_SIZE: usize = u16::MAX as usize;
+
+/// A transfer over whole buffers, done as consecutive calls of the HAL when it is longer than `CHUNK_SIZE`.
+#[derive(Clone, Copy)]
+pub struct Chunks
+{
+    tx: *const u8,
+    rx: *const u8,
+    unit: usize,
+    count: usize,
+    offset: usize,
+}
+
+/// One call of the HAL within a transfer.
+#[derive(Clone, Copy)]
+pub struct Chunk
+{
+    pub tx: *const u8,
+    pub rx: *const u8,
+    pub size: u16,
+    /// The frames transferred before this chunk.
+    pub offset: usize,
+}
+
+impl Chunks
+{
+    /// A transfer of `count` frames of `unit` bytes, the buffer of an unused direction is null.
+    pub fn new(tx: *const u8, rx: *const u8, count: usize, unit: usize) -> Self
+    {
+        Chunks {
+            tx,
+            rx,
+            unit,
+            count,
+            offset: 0,
+        }
+    }
+
+    /// Whether the transfer needs more than one call of the HAL.
+    pub fn is_long(&self) -> bool
+    {
+        self.count > CHUNK_SIZE
+    }
+
+    pub fn is_done(&self) -> bool
+    {
+        self.offset >= self.count
+    }
+
+    /// The next chunk, an empty one for an empty transfer, which the HAL refuses.
+    pub fn next(&mut self) -> Chunk
+    {
+        let size = (self.count - self.offset).min(CHUNK_SIZE);
+
+        let chunk = Chunk {
+            tx: self.tx.wrapping_add(self.offset * self.unit),
+            rx: self.rx.wrapping_add(self.offset * self.unit),
+            size: size as u16,
+            offset: self.offset,
+        };
+
+        self.offset += size;
+
+        chunk
+    }
+
+    /// Run the chunks one after the other with blocking calls, stopping at the first failure.
+    pub fn run<F>(mut self, mut transfer: F) -> Result<()>
+    where
+        F: FnMut(Chunk) -> Result<()>,
+    {
+        loop {
+            transfer(self.next())?;
+
+            if self.is_done() {
+                return Ok(());
+            }
+        }
+    }
+}
@@ -0,0 +1,15 @@
+use crate::hal::system::SystemCoreClock;
+
+/// Busy wait at least `ns` nanoseconds, one turn of the loop takes no less than one core clock.
+pub fn delay_ns(ns: u32)
+{
+    if ns == 0 {
+        return;
+    }
+
+    let cycles = unsafe { core::ptr::read_volatile(core::ptr::addr_of!(SystemCoreClock)) } as u64 * ns as u64 / 1_000_000_000 + 1;
+
+    for _ in 0..cycles {
+        core::hint::spin_loop();
+    }
+}
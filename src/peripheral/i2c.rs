@@ -1,3 +1,30 @@
+pub mod eeprom;
+pub mod smbus;
+
+mod i2c_address;
+mod i2c_arbitration;
+mod i2c_config;
+mod i2c_recovery;
+mod i2c_register;
+mod i2c_scan;
+mod i2c_transaction;
+mod i2c_transfer;
+
+pub use i2c_address::I2cAddress;
+pub use i2c_address::I2cMemoryWidth;
+pub use i2c_arbitration::I2cArbitrationLostHandle;
+pub use i2c_config::I2cConfig;
+pub use i2c_config::I2cDutyCycle;
+pub use i2c_config::I2C_FAST_MODE_SPEED;
+pub use i2c_config::I2C_STANDARD_MODE_SPEED;
+pub use i2c_register::I2cRegisterEvent;
+pub use i2c_register::I2cRegisterEventHandle;
+pub use i2c_scan::I2cScanEvent;
+pub use i2c_scan::I2cScanEventHandle;
+pub use i2c_transaction::I2cSegment;
+
+use core::ptr::null;
+
 use escw_mcu::common::Error;
 use escw_mcu::common::Result;
 use escw_mcu::peripheral::i2c::I2cEventHandle;
@@ -5,6 +32,13 @@ use escw_mcu::peripheral::i2c::I2cMasterDevice;
 use escw_mcu::peripheral::i2c::I2cSlaveDevice;
 
 use crate::hal::i2c::*;
+use crate::peripheral::chunk::Chunks;
+use crate::peripheral::chunk::CHUNK_SIZE;
+
+use i2c_arbitration::I2cRetry;
+use i2c_recovery::I2cRecovery;
+use i2c_transfer::I2cTransfer;
+use i2c_transfer::I2cTransferKind;
 
 #[derive(Clone, Copy)]
 pub enum I2cIdentifies
@@ -25,20 +59,26 @@ impl I2cIdentifies
     }
 }
 
+impl Into<*mut Hi2c> for I2cIdentifies
+{
+    fn into(self) -> *mut Hi2c
+    {
+        match self {
+            #[cfg(feature = "i2c1")]
+            Self::I2c1 => core::ptr::addr_of_mut!(hi2c1),
+            #[cfg(feature = "i2c2")]
+            Self::I2c2 => core::ptr::addr_of_mut!(hi2c2),
+            #[cfg(feature = "i2c3")]
+            Self::I2c3 => core::ptr::addr_of_mut!(hi2c3),
+        }
+    }
+}
+
 impl Into<&Hi2c> for I2cIdentifies
 {
     fn into(self) -> &'static Hi2c
     {
-        unsafe {
-            match self {
-                #[cfg(feature = "i2c1")]
-                Self::I2c1 => &hi2c1,
-                #[cfg(feature = "i2c2")]
-                Self::I2c2 => &hi2c2,
-                #[cfg(feature = "i2c3")]
-                Self::I2c3 => &hi2c3,
-            }
-        }
+        unsafe { &*Into::<*mut Hi2c>::into(self) }
     }
 }
 
@@ -60,16 +100,183 @@ impl TryInto<I2cIdentifies> for &Hi2c
     }
 }
 
+/// The errors of the last transfer, as the HAL tells them.
+#[derive(Clone, Copy)]
+pub struct I2cError(u32);
+
+impl I2cError
+{
+    pub fn bus(&self) -> bool
+    {
+        self.0 & HAL_I2C_ERROR_BERR != 0
+    }
+
+    /// Another master has won the bus, the I2C has left the transfer and become a slave.
+    pub fn arbitration_lost(&self) -> bool
+    {
+        self.0 & HAL_I2C_ERROR_ARLO != 0
+    }
+
+    pub fn nack(&self) -> bool
+    {
+        self.0 & HAL_I2C_ERROR_AF != 0
+    }
+
+    pub fn overrun(&self) -> bool
+    {
+        self.0 & HAL_I2C_ERROR_OVR != 0
+    }
+
+    pub fn dma(&self) -> bool
+    {
+        self.0 & HAL_I2C_ERROR_DMA != 0
+    }
+
+    pub fn timeout(&self) -> bool
+    {
+        self.0 & HAL_I2C_ERROR_TIMEOUT != 0
+    }
+}
+
+impl From<u32> for I2cError
+{
+    fn from(value: u32) -> Self
+    {
+        I2cError(value)
+    }
+}
+
+impl Into<u32> for I2cError
+{
+    fn into(self) -> u32
+    {
+        self.0
+    }
+}
+
 pub struct I2cMaster
 {
     i2c: I2cIdentifies,
+    recovery: Option<I2cRecovery>,
+    retry: Option<I2cRetry>,
 }
 
 impl I2cMaster
 {
     pub fn new(i2c: I2cIdentifies) -> Self
     {
-        I2cMaster { i2c }
+        I2cMaster {
+            i2c,
+            recovery: None,
+            retry: None,
+        }
+    }
+
+    pub fn write(&self, address: I2cAddress, data: &[u8], timeout: u32) -> Result<()>
+    {
+        let device = self.target(address)?;
+
+        self.send(device, data, timeout)
+    }
+
+    pub fn read(&self, address: I2cAddress, data: &mut [u8], timeout: u32) -> Result<()>
+    {
+        let device = self.target(address)?;
+
+        self.receive(device, data, timeout)
+    }
+
+    pub fn write_memory(&self, address: I2cAddress, memory: u16, width: I2cMemoryWidth, data: &[u8], timeout: u32) -> Result<()>
+    {
+        let device = self.target(address)?;
+
+        self.memory_write(device, memory, width.into(), data, timeout)
+    }
+
+    pub fn read_memory(&self, address: I2cAddress, memory: u16, width: I2cMemoryWidth, data: &mut [u8], timeout: u32) -> Result<()>
+    {
+        let device = self.target(address)?;
+
+        self.memory_read(device, memory, width.into(), data, timeout)
+    }
+
+    pub fn write_with_interrupt(&self, address: I2cAddress, data: &[u8]) -> Result<()>
+    {
+        let device = self.target(address)?;
+
+        self.send_with_interrupt(device, data)
+    }
+
+    pub fn read_with_interrupt(&self, address: I2cAddress, data: &mut [u8]) -> Result<()>
+    {
+        let device = self.target(address)?;
+
+        self.receive_with_interrupt(device, data)
+    }
+
+    pub fn write_memory_with_interrupt(&self, address: I2cAddress, memory: u16, width: I2cMemoryWidth, data: &[u8]) -> Result<()>
+    {
+        let device = self.target(address)?;
+
+        self.memory_write_with_interrupt(device, memory, width.into(), data)
+    }
+
+    pub fn read_memory_with_interrupt(&self, address: I2cAddress, memory: u16, width: I2cMemoryWidth, data: &mut [u8]) -> Result<()>
+    {
+        let device = self.target(address)?;
+
+        self.memory_read_with_interrupt(device, memory, width.into(), data)
+    }
+
+    pub fn write_with_dma(&self, address: I2cAddress, data: &[u8]) -> Result<()>
+    {
+        let device = self.target(address)?;
+
+        self.send_with_dma(device, data)
+    }
+
+    pub fn read_with_dma(&self, address: I2cAddress, data: &mut [u8]) -> Result<()>
+    {
+        let device = self.target(address)?;
+
+        self.receive_with_dma(device, data)
+    }
+
+    pub fn write_memory_with_dma(&self, address: I2cAddress, memory: u16, width: I2cMemoryWidth, data: &[u8]) -> Result<()>
+    {
+        let device = self.target(address)?;
+
+        self.memory_write_with_dma(device, memory, width.into(), data)
+    }
+
+    pub fn read_memory_with_dma(&self, address: I2cAddress, memory: u16, width: I2cMemoryWidth, data: &mut [u8]) -> Result<()>
+    {
+        let device = self.target(address)?;
+
+        self.memory_read_with_dma(device, memory, width.into(), data)
+    }
+
+    /// Switch to the addressing mode of `address`, and give the address as the HAL takes it.
+    ///
+    /// The methods of `I2cMasterDevice` take the addresses of the HAL as they are, in the current mode.
+    fn target(&self, address: I2cAddress) -> Result<u16>
+    {
+        let (device, mode) = address.hal()?;
+
+        addressing(self.i2c, mode)?;
+
+        Ok(device)
+    }
+
+    fn run(&self, kind: I2cTransferKind, chunks: Chunks, timeout: u32) -> Result<()>
+    {
+        self.watch(self.retry(|| i2c_transfer::run(self.i2c, kind, chunks, timeout)))
+    }
+
+    /// Start an interrupt or DMA transfer, only its last chunk sends the end event.
+    fn start(&self, kind: I2cTransferKind, dma: bool, chunks: Chunks) -> Result<()>
+    {
+        self.watch(I2cTransfer::new(kind, dma, chunks).start(self.i2c))
     }
 }
 
@@ -82,67 +289,67 @@ impl I2cMasterDevice for I2cMaster
 
     fn device_state(&self, device: u16, trails: u32, timeout: u32) -> Result<()>
     {
-        unsafe { HAL_I2C_IsDeviceReady(self.i2c.into(), device, trails, timeout).into() }
+        self.watch(unsafe { HAL_I2C_IsDeviceReady(self.i2c.into(), device, trails, timeout).into() })
     }
 
     fn send(&self, device: u16, data: &[u8], timeout: u32) -> Result<()>
     {
-        unsafe { HAL_I2C_Master_Transmit(self.i2c.into(), device, data.as_ptr(), data.len() as u16, timeout).into() }
+        self.run(I2cTransferKind::Transmit(device), Chunks::new(data.as_ptr(), null(), data.len(), 1), timeout)
     }
 
     fn receive(&self, device: u16, data: &mut [u8], timeout: u32) -> Result<()>
     {
-        unsafe { HAL_I2C_Master_Receive(self.i2c.into(), device, data.as_ptr(), data.len() as u16, timeout).into() }
+        self.run(I2cTransferKind::Receive(device), Chunks::new(null(), data.as_ptr(), data.len(), 1), timeout)
     }
 
     fn memory_write(&self, device: u16, address: u16, wide: u16, data: &[u8], timeout: u32) -> Result<()>
     {
-        unsafe { HAL_I2C_Mem_Write(self.i2c.into(), device, address, wide, data.as_ptr(), data.len() as u16, timeout).into() }
+        self.run(I2cTransferKind::MemoryWrite(device, address, wide), Chunks::new(data.as_ptr(), null(), data.len(), 1), timeout)
     }
 
     fn memory_read(&self, device: u16, address: u16, wide: u16, data: &mut [u8], timeout: u32) -> Result<()>
     {
-        unsafe { HAL_I2C_Mem_Read(self.i2c.into(), device, address, wide, data.as_ptr(), data.len() as u16, timeout).into() }
+        self.run(I2cTransferKind::MemoryRead(device, address, wide), Chunks::new(null(), data.as_ptr(), data.len(), 1), timeout)
     }
 
     fn send_with_interrupt(&self, device: u16, data: &[u8]) -> Result<()>
     {
-        unsafe { HAL_I2C_Master_Transmit_IT(self.i2c.into(), device, data.as_ptr(), data.len() as u16).into() }
+        self.start(I2cTransferKind::Transmit(device), false, Chunks::new(data.as_ptr(), null(), data.len(), 1))
     }
 
     fn receive_with_interrupt(&self, device: u16, data: &mut [u8]) -> Result<()>
     {
-        unsafe { HAL_I2C_Master_Receive_IT(self.i2c.into(), device, data.as_ptr(), data.len() as u16).into() }
+        self.start(I2cTransferKind::Receive(device), false, Chunks::new(null(), data.as_ptr(), data.len(), 1))
     }
 
     fn memory_write_with_interrupt(&self, device: u16, address: u16, wide: u16, data: &[u8]) -> Result<()>
     {
-        unsafe { HAL_I2C_Mem_Write_IT(self.i2c.into(), device, address, wide, data.as_ptr(), data.len() as u16).into() }
+        self.start(I2cTransferKind::MemoryWrite(device, address, wide), false, Chunks::new(data.as_ptr(), null(), data.len(), 1))
     }
 
     fn memory_read_with_interrupt(&self, device: u16, address: u16, wide: u16, data: &mut [u8]) -> Result<()>
     {
-        unsafe { HAL_I2C_Mem_Read_IT(self.i2c.into(), device, address, wide, data.as_ptr(), data.len() as u16).into() }
+        self.start(I2cTransferKind::MemoryRead(device, address, wide), false, Chunks::new(null(), data.as_ptr(), data.len(), 1))
     }
 
     fn send_with_dma(&self, device: u16, data: &[u8]) -> Result<()>
     {
-        unsafe { HAL_I2C_Master_Transmit_DMA(self.i2c.into(), device, data.as_ptr(), data.len() as u16).into() }
+        self.start(I2cTransferKind::Transmit(device), true, Chunks::new(data.as_ptr(), null(), data.len(), 1))
     }
 
     fn receive_with_dma(&self, device: u16, data: &mut [u8]) -> Result<()>
     {
-        unsafe { HAL_I2C_Master_Receive_DMA(self.i2c.into(), device, data.as_ptr(), data.len() as u16).into() }
+        self.start(I2cTransferKind::Receive(device), true, Chunks::new(null(), data.as_ptr(), data.len(), 1))
     }
 
     fn memory_write_with_dma(&self, device: u16, address: u16, wide: u16, data: &[u8]) -> Result<()>
     {
-        unsafe { HAL_I2C_Mem_Write_DMA(self.i2c.into(), device, address, wide, data.as_ptr(), data.len() as u16).into() }
+        self.start(I2cTransferKind::MemoryWrite(device, address, wide), true, Chunks::new(data.as_ptr(), null(), data.len(), 1))
     }
 
     fn memory_read_with_dma(&self, device: u16, address: u16, wide: u16, data: &mut [u8]) -> Result<()>
     {
-        unsafe { HAL_I2C_Mem_Read_DMA(self.i2c.into(), device, address, wide, data.as_ptr(), data.len() as u16).into() }
+        self.start(I2cTransferKind::MemoryRead(device, address, wide), true, Chunks::new(null(), data.as_ptr(), data.len(), 1))
     }
 
     fn abort(&self, device: u16) -> Result<()>
@@ -173,32 +380,44 @@ impl I2cSlaveDevice for I2cSlave
 
     fn send(&self, data: &[u8], timeout: u32) -> Result<()>
     {
-        unsafe { HAL_I2C_Slave_Transmit(self.i2c.into(), data.as_ptr(), data.len() as u16, timeout).into() }
+        let size = slave_size(data.len())?;
+
+        unsafe { HAL_I2C_Slave_Transmit(self.i2c.into(), data.as_ptr(), size, timeout).into() }
     }
 
     fn receive(&self, data: &mut [u8], timeout: u32) -> Result<()>
     {
-        unsafe { HAL_I2C_Slave_Receive(self.i2c.into(), data.as_ptr(), data.len() as u16, timeout).into() }
+        let size = slave_size(data.len())?;
+
+        unsafe { HAL_I2C_Slave_Receive(self.i2c.into(), data.as_ptr(), size, timeout).into() }
     }
 
     fn send_with_interrupt(&self, data: &[u8]) -> Result<()>
     {
-        unsafe { HAL_I2C_Slave_Transmit_IT(self.i2c.into(), data.as_ptr(), data.len() as u16).into() }
+        let size = slave_size(data.len())?;
+
+        unsafe { HAL_I2C_Slave_Transmit_IT(self.i2c.into(), data.as_ptr(), size).into() }
     }
 
     fn receive_with_interrupt(&self, data: &mut [u8]) -> Result<()>
     {
-        unsafe { HAL_I2C_Slave_Receive_IT(self.i2c.into(), data.as_ptr(), data.len() as u16).into() }
+        let size = slave_size(data.len())?;
+
+        unsafe { HAL_I2C_Slave_Receive_IT(self.i2c.into(), data.as_ptr(), size).into() }
     }
 
     fn send_with_dma(&self, data: &[u8]) -> Result<()>
     {
-        unsafe { HAL_I2C_Slave_Transmit_DMA(self.i2c.into(), data.as_ptr(), data.len() as u16).into() }
+        let size = slave_size(data.len())?;
+
+        unsafe { HAL_I2C_Slave_Transmit_DMA(self.i2c.into(), data.as_ptr(), size).into() }
     }
 
     fn receive_with_dma(&self, data: &mut [u8]) -> Result<()>
     {
-        unsafe { HAL_I2C_Slave_Receive_DMA(self.i2c.into(), data.as_ptr(), data.len() as u16).into() }
+        let size = slave_size(data.len())?;
+
+        unsafe { HAL_I2C_Slave_Receive_DMA(self.i2c.into(), data.as_ptr(), size).into() }
     }
 
     fn listen(&self) -> Result<()>
@@ -207,10 +426,50 @@ impl I2cSlaveDevice for I2cSlave
     }
 }
 
+/// Initialize the I2C again with another addressing mode, unless it already has it.
+///
+/// The initialization resets the I2C, so it is refused while a transfer or a listen is running.
+fn addressing(i2c: I2cIdentifies, mode: u32) -> Result<()>
+{
+    let hi2c: *mut Hi2c = i2c.into();
+
+    unsafe {
+        if (*hi2c).init.addressing_mode == mode {
+            return Ok(());
+        }
+
+        if !matches!(HAL_I2C_GetState(&*hi2c), I2cState::Ready) {
+            return Err(Error::PeripheralBusy);
+        }
+
+        (*hi2c).init.addressing_mode = mode;
+
+        HAL_I2C_Init(&*hi2c).into()
+    }
+}
+
+/// A slave cannot go on with a transfer of the master in another call of the HAL, without listening.
+fn slave_size(size: usize) -> Result<u16>
+{
+    if size > CHUNK_SIZE {
+        return Err(Error::Param);
+    }
+
+    Ok(size as u16)
+}
+
 mod event
 {
+    use super::i2c_arbitration::I2cArbitrationLostHandle;
+    use super::i2c_register::I2cRegisterMap;
+    use super::i2c_scan::I2cScan;
+    use super::i2c_transaction::I2cChain;
+    use super::i2c_transfer::I2cTransfer;
+    use super::I2cError;
     use super::I2cIdentifies;
     use crate::hal::i2c::Hi2c;
+    use crate::hal::i2c::HAL_I2C_GetError;
+    use crate::hal::i2c::I2C_ADDRESSINGMODE_7BIT;
     use escw_mcu::peripheral::i2c::I2cDirection;
     use escw_mcu::peripheral::i2c::I2cEvent;
     use escw_mcu::peripheral::i2c::I2cEventHandle;
@@ -220,6 +479,11 @@ mod event
     pub struct EventCenter
     {
         handle: [Option<I2cEventHandle>; I2cIdentifies::count()],
+        transfer: [Option<I2cTransfer>; I2cIdentifies::count()],
+        chain: [Option<I2cChain>; I2cIdentifies::count()],
+        scan: [Option<I2cScan>; I2cIdentifies::count()],
+        registers: [Option<I2cRegisterMap>; I2cIdentifies::count()],
+        arbitration: [Option<I2cArbitrationLostHandle>; I2cIdentifies::count()],
     }
 
     impl EventCenter
@@ -228,6 +492,11 @@ mod event
         {
             EventCenter {
                 handle: [None; I2cIdentifies::count()],
+                transfer: [None; I2cIdentifies::count()],
+                chain: [None; I2cIdentifies::count()],
+                scan: [None; I2cIdentifies::count()],
+                registers: [None; I2cIdentifies::count()],
+                arbitration: [None; I2cIdentifies::count()],
             }
         }
 
@@ -238,9 +507,180 @@ mod event
             }
         }
 
-        pub fn invoke(i2c: I2cIdentifies, event: I2cEvent)
+        /// Keep a transfer too long for one call of the HAL, its end events start the next chunks.
+        pub fn follow(i2c: I2cIdentifies, transfer: Option<I2cTransfer>)
+        {
+            unsafe {
+                EVENT_CENTER.transfer[i2c as usize] = transfer;
+            }
+        }
+
+        /// Keep a transaction of several segments, its end events start the next segments.
+        pub fn chain(i2c: I2cIdentifies, chain: Option<I2cChain>)
+        {
+            unsafe {
+                EVENT_CENTER.chain[i2c as usize] = chain;
+            }
+        }
+
+        /// Whether the blocking transfer or transaction kept by the center has succeeded, `None` until it ends.
+        pub fn outcome(i2c: I2cIdentifies) -> Option<bool>
+        {
+            unsafe {
+                let transfer = core::ptr::read_volatile(core::ptr::addr_of!(EVENT_CENTER.transfer[i2c as usize]));
+                let chain = core::ptr::read_volatile(core::ptr::addr_of!(EVENT_CENTER.chain[i2c as usize]));
+
+                transfer.and_then(|transfer| transfer.succeeded).or(chain.and_then(|chain| chain.succeeded))
+            }
+        }
+
+        /// Forget the blocking transfer or transaction, once it has ended or timed out.
+        pub fn forget(i2c: I2cIdentifies)
+        {
+            Self::follow(i2c, None);
+            Self::chain(i2c, None);
+        }
+
+        /// Keep an asynchronous scan, which takes the end events of its probes.
+        pub fn scan(i2c: I2cIdentifies, scan: Option<I2cScan>)
+        {
+            unsafe {
+                EVENT_CENTER.scan[i2c as usize] = scan;
+            }
+        }
+
+        pub fn is_scanning(i2c: I2cIdentifies) -> bool
+        {
+            unsafe { (*core::ptr::addr_of!(EVENT_CENTER.scan[i2c as usize])).is_some() }
+        }
+
+        /// Keep the registers served by a slave, which take all its events.
+        pub fn registers(i2c: I2cIdentifies, registers: Option<I2cRegisterMap>)
+        {
+            unsafe {
+                EVENT_CENTER.registers[i2c as usize] = registers;
+            }
+        }
+
+        pub fn register_map(i2c: I2cIdentifies) -> Option<&'static mut I2cRegisterMap>
+        {
+            unsafe { (*core::ptr::addr_of_mut!(EVENT_CENTER.registers[i2c as usize])).as_mut() }
+        }
+
+        pub fn arbitration(i2c: I2cIdentifies, handle: Option<I2cArbitrationLostHandle>)
+        {
+            unsafe {
+                EVENT_CENTER.arbitration[i2c as usize] = handle;
+            }
+        }
+
+        /// The end of the transfer of a listening slave, after the STOP.
+        pub fn listen_completed(i2c: I2cIdentifies)
+        {
+            unsafe {
+                if let Some(registers) = (*core::ptr::addr_of_mut!(EVENT_CENTER.registers[i2c as usize])).as_mut() {
+                    registers.end(i2c.into());
+                }
+            }
+        }
+
+        pub fn invoke(i2c: I2cIdentifies, mut event: I2cEvent)
+        {
+            unsafe {
+                if let Some(registers) = (*core::ptr::addr_of_mut!(EVENT_CENTER.registers[i2c as usize])).as_mut() {
+                    registers.on_event(i2c, event);
+                    return;
+                }
+
+                let scan = &mut *core::ptr::addr_of_mut!(EVENT_CENTER.scan[i2c as usize]);
+
+                if let Some(current) = scan.as_mut() {
+                    let present = match event {
+                        I2cEvent::TxCompleted => true,
+                        I2cEvent::Error => false,
+                        I2cEvent::TxRxAborted => {
+                            *scan = None;
+                            let _ = super::addressing(i2c, I2C_ADDRESSINGMODE_7BIT);
+                            Self::forward(i2c, event);
+                            return;
+                        }
+                        _ => return,
+                    };
+
+                    if !current.advance(i2c, present) {
+                        *scan = None;
+                    }
+
+                    return;
+                }
+
+                let chain = &mut *core::ptr::addr_of_mut!(EVENT_CENTER.chain[i2c as usize]);
+
+                if let Some(current) = chain.as_mut() {
+                    match event {
+                        I2cEvent::TxCompleted | I2cEvent::RxCompleted => match current.advance(i2c) {
+                            Ok(true) => return,
+                            Ok(false) => {}
+                            Err(_) => event = I2cEvent::Error,
+                        },
+                        I2cEvent::Error | I2cEvent::TxRxAborted => {}
+                        _ => {
+                            Self::forward(i2c, event);
+                            return;
+                        }
+                    }
+
+                    if current.is_blocking() {
+                        current.succeeded = Some(!matches!(event, I2cEvent::Error | I2cEvent::TxRxAborted));
+                        return;
+                    }
+
+                    *chain = None;
+                    Self::forward(i2c, event);
+                    return;
+                }
+
+                let transfer = &mut *core::ptr::addr_of_mut!(EVENT_CENTER.transfer[i2c as usize]);
+
+                if let Some(current) = transfer.as_mut() {
+                    match event {
+                        I2cEvent::TxCompleted | I2cEvent::RxCompleted | I2cEvent::MemoryWriteCompleted | I2cEvent::MemoryReadCompleted => {
+                            match current.advance(i2c) {
+                                Ok(true) => return,
+                                Ok(false) => {}
+                                Err(_) => event = I2cEvent::Error,
+                            }
+                        }
+                        I2cEvent::Error | I2cEvent::TxRxAborted => {}
+                        _ => {
+                            Self::forward(i2c, event);
+                            return;
+                        }
+                    }
+
+                    // The caller of a blocking transfer takes its end, and forgets the transfer.
+                    if current.is_blocking() {
+                        current.succeeded = Some(!matches!(event, I2cEvent::Error | I2cEvent::TxRxAborted));
+                        return;
+                    }
+
+                    *transfer = None;
+                }
+
+                Self::forward(i2c, event);
+            }
+        }
+
+        fn forward(i2c: I2cIdentifies, event: I2cEvent)
         {
             unsafe {
+                if let (I2cEvent::Error, Some(lost)) = (&event, EVENT_CENTER.arbitration[i2c as usize]) {
+                    if I2cError::from(HAL_I2C_GetError(i2c.into())).arbitration_lost() {
+                        lost(i2c);
+                        return;
+                    }
+                }
+
                 if let Some(invoke) = EVENT_CENTER.handle[i2c as usize].as_ref() {
                     invoke(event);
                 }
@@ -291,8 +731,13 @@ mod event
         }
     }
 
-    // #[no_mangle]
-    // pub extern "C" fn HAL_I2C_ListenCpltCallback(hi2c: &Hi2c) {}
+    #[no_mangle]
+    pub extern "C" fn HAL_I2C_ListenCpltCallback(hi2c: &Hi2c)
+    {
+        if let Some(i2c) = hi2c.try_into().ok() {
+            EventCenter::listen_completed(i2c);
+        }
+    }
 
     #[no_mangle]
     pub extern "C" fn HAL_I2C_MemTxCpltCallback(hi2c: &Hi2c)
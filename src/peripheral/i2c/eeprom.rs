@@ -0,0 +1,220 @@
+use escw_mcu::common::Error;
+use escw_mcu::common::Result;
+use escw_mcu::peripheral::i2c::I2cMasterDevice;
+
+use crate::hal::system::HAL_GetTick;
+
+use super::I2cAddress;
+use super::I2cMaster;
+use super::I2cMemoryWidth;
+
+/// The timeout of each transfer with the EEPROM.
+const EEPROM_TIMEOUT: u32 = 100;
+
+/// The longest write cycle of a 24Cxx, after which it acknowledges its address again.
+const EEPROM_WRITE_CYCLE: u32 = 10;
+
+/// The size of a block of the models with a one byte memory address.
+const BLOCK_SIZE: u32 = 256;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Eeprom24Model
+{
+    C01,
+    C02,
+    C04,
+    C08,
+    C16,
+    C32,
+    C64,
+    C128,
+    C256,
+    C512,
+}
+
+impl Eeprom24Model
+{
+    /// The size of the memory in bytes.
+    pub const fn size(&self) -> u32
+    {
+        match self {
+            Self::C01 => 128,
+            Self::C02 => 256,
+            Self::C04 => 512,
+            Self::C08 => 1024,
+            Self::C16 => 2048,
+            Self::C32 => 4096,
+            Self::C64 => 8192,
+            Self::C128 => 16384,
+            Self::C256 => 32768,
+            Self::C512 => 65536,
+        }
+    }
+
+    /// The size of a page, a write never goes past the end of its page.
+    pub const fn page_size(&self) -> u32
+    {
+        match self {
+            Self::C01 | Self::C02 => 8,
+            Self::C04 | Self::C08 | Self::C16 => 16,
+            Self::C32 | Self::C64 => 32,
+            Self::C128 | Self::C256 => 64,
+            Self::C512 => 128,
+        }
+    }
+
+    pub const fn width(&self) -> I2cMemoryWidth
+    {
+        match self {
+            Self::C01 | Self::C02 | Self::C04 | Self::C08 | Self::C16 => I2cMemoryWidth::Bits8,
+            _ => I2cMemoryWidth::Bits16,
+        }
+    }
+}
+
+/// A 24Cxx EEPROM on an I2C master.
+///
+/// The writes are split at the page boundaries, each page is written then the EEPROM is polled until it
+/// acknowledges its address again at the end of its write cycle. The 24C04, 24C08 and 24C16 take the upper
+/// bits of the memory address in the device address, as blocks of 256 bytes at consecutive addresses.
+///
+/// As a flash, the sectors of `FlashDevice::erase` are the pages, erased to 0xFF, and the bank is ignored.
+pub struct Eeprom24
+{
+    master: I2cMaster,
+    model: Eeprom24Model,
+    address: u8,
+}
+
+impl Eeprom24
+{
+    /// Take the EEPROM of `model` at the 7 bits `address`, 0x50 with the address pins of the chip.
+    pub fn new(master: I2cMaster, model: Eeprom24Model, address: u8) -> Self
+    {
+        Eeprom24 { master, model, address }
+    }
+
+    pub fn master(&self) -> &I2cMaster
+    {
+        &self.master
+    }
+
+    pub fn model(&self) -> Eeprom24Model
+    {
+        self.model
+    }
+
+    /// Read `data` from `address` on, across the pages and the blocks.
+    pub fn read(&self, address: u32, data: &mut [u8]) -> Result<()>
+    {
+        self.check(address, data.len())?;
+
+        let mut offset = 0;
+
+        while offset < data.len() {
+            let memory = address + offset as u32;
+            let size = match self.model.width() {
+                I2cMemoryWidth::Bits8 => (BLOCK_SIZE - memory % BLOCK_SIZE) as usize,
+                I2cMemoryWidth::Bits16 => data.len(),
+            }
+            .min(data.len() - offset);
+
+            let (device, memory) = self.locate(memory);
+            self.master.read_memory(device, memory, self.model.width(), &mut data[offset..offset + size], EEPROM_TIMEOUT)?;
+
+            offset += size;
+        }
+
+        Ok(())
+    }
+
+    /// Write `data` from `address` on, one page after the other, and wait for the last write cycle.
+    pub fn write(&self, address: u32, data: &[u8]) -> Result<()>
+    {
+        self.check(address, data.len())?;
+
+        let mut offset = 0;
+
+        while offset < data.len() {
+            let memory = address + offset as u32;
+            let size = ((self.model.page_size() - memory % self.model.page_size()) as usize).min(data.len() - offset);
+
+            let (device, memory) = self.locate(memory);
+            self.master.write_memory(device, memory, self.model.width(), &data[offset..offset + size], EEPROM_TIMEOUT)?;
+            self.wait_write_cycle(device)?;
+
+            offset += size;
+        }
+
+        Ok(())
+    }
+
+    /// Fill `count` pages from `page` on with 0xFF.
+    pub fn erase(&self, page: u32, count: u32) -> Result<()>
+    {
+        let size = self.model.page_size();
+        let erased = [0xFF; 128];
+
+        let address = page.checked_mul(size).ok_or(Error::Param)?;
+        let length = count.checked_mul(size).ok_or(Error::Param)?;
+        let end = page.checked_add(count).ok_or(Error::Param)?;
+
+        self.check(address, length as usize)?;
+
+        for index in page..end {
+            self.write(index * size, &erased[..size as usize])?;
+        }
+
+        Ok(())
+    }
+
+    fn check(&self, address: u32, size: usize) -> Result<()>
+    {
+        if address as u64 + size as u64 > self.model.size() as u64 {
+            return Err(Error::Param);
+        }
+
+        Ok(())
+    }
+
+    /// The device address and the memory address of a byte of the EEPROM.
+    fn locate(&self, address: u32) -> (I2cAddress, u16)
+    {
+        match self.model.width() {
+            I2cMemoryWidth::Bits8 => (I2cAddress::SevenBit(self.address | (address / BLOCK_SIZE) as u8), (address % BLOCK_SIZE) as u16),
+            I2cMemoryWidth::Bits16 => (I2cAddress::SevenBit(self.address), address as u16),
+        }
+    }
+
+    /// Poll the EEPROM until it acknowledges its address, which it does not during its write cycle.
+    fn wait_write_cycle(&self, device: I2cAddress) -> Result<()>
+    {
+        let (device, _) = device.hal()?;
+
+        let start = unsafe { HAL_GetTick() };
+
+        loop {
+            if self.master.device_state(device, 1, EEPROM_TIMEOUT).is_ok() {
+                return Ok(());
+            }
+
+            if unsafe { HAL_GetTick() }.wrapping_sub(start) > EEPROM_WRITE_CYCLE {
+                return Err(Error::WaitTimeout);
+            }
+        }
+    }
+}
+
+#[cfg(feature = "flash")]
+impl escw_mcu::peripheral::flash::FlashDevice for Eeprom24
+{
+    fn erase(&self, _bank: u32, sector: u32, count: u32) -> Result<()>
+    {
+        Eeprom24::erase(self, sector, count)
+    }
+
+    fn program(&self, address: u32, data: &[u8]) -> Result<()>
+    {
+        self.write(address, data)
+    }
+}
@@ -0,0 +1,44 @@
+use escw_mcu::common::Error;
+use escw_mcu::common::Result;
+
+use crate::hal::i2c::*;
+
+/// The address of a device, as written in its datasheet, without the read/write bit.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum I2cAddress
+{
+    SevenBit(u8),
+    TenBit(u16),
+}
+
+impl I2cAddress
+{
+    /// The address as the HAL takes it, a 7 bits one shifted left by one, with its addressing mode.
+    pub(super) fn hal(&self) -> Result<(u16, u32)>
+    {
+        match *self {
+            Self::SevenBit(address) if address <= 0x7F => Ok(((address as u16) << 1, I2C_ADDRESSINGMODE_7BIT)),
+            Self::TenBit(address) if address <= 0x3FF => Ok((address, I2C_ADDRESSINGMODE_10BIT)),
+            _ => Err(Error::Param),
+        }
+    }
+}
+
+/// The size of the memory addresses of a device.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum I2cMemoryWidth
+{
+    Bits8,
+    Bits16,
+}
+
+impl Into<u16> for I2cMemoryWidth
+{
+    fn into(self) -> u16
+    {
+        match self {
+            Self::Bits8 => I2C_MEMADD_SIZE_8BIT,
+            Self::Bits16 => I2C_MEMADD_SIZE_16BIT,
+        }
+    }
+}
@@ -0,0 +1,126 @@
+use escw_mcu::common::Result;
+
+use crate::hal::i2c::*;
+use crate::hal::system::HAL_Delay;
+
+use super::event::EventCenter;
+use super::I2cError;
+use super::I2cIdentifies;
+use super::I2cMaster;
+use super::I2cSlave;
+
+/// Told instead of `I2cEvent::Error` when a transfer with interrupts or DMA has lost the arbitration.
+pub type I2cArbitrationLostHandle = fn(I2cIdentifies);
+
+/// How the blocking transfers of a master are tried again after losing the arbitration.
+pub struct I2cRetry
+{
+    retries: u32,
+    backoff: u32,
+}
+
+impl I2cMaster
+{
+    /// Try a blocking transfer again up to `retries` times when another master wins the bus, after waiting
+    /// `backoff` ms, doubled at each try, for the other transfer to end. A transfer longer than one call of
+    /// the HAL is tried again from its start.
+    pub fn with_arbitration_retry(&mut self, retries: u32, backoff: u32)
+    {
+        self.retry = Some(I2cRetry { retries, backoff });
+    }
+
+    /// Send the lost arbitrations of the transfers with interrupts or DMA to `handle`, rather than
+    /// `I2cEvent::Error` to the handle of `with_event`, for the caller to start them again.
+    pub fn with_arbitration_event(&self, handle: I2cArbitrationLostHandle)
+    {
+        EventCenter::arbitration(self.i2c, Some(handle));
+    }
+
+    /// The error of the last transfer, to be read from the handle of `I2cEvent::Error` or after a failed
+    /// blocking transfer.
+    pub fn error(&self) -> I2cError
+    {
+        I2cError::from(unsafe { HAL_I2C_GetError(self.i2c.into()) })
+    }
+
+    /// Take the I2C as a slave, the master is given back while a transfer is running.
+    ///
+    /// Both roles share the same initialization, so the I2C is not initialized again. The arbitration and
+    /// recovery settings of the master are dropped.
+    pub fn into_slave(self) -> core::result::Result<I2cSlave, I2cMaster>
+    {
+        match unsafe { HAL_I2C_GetState(self.i2c.into()) } {
+            I2cState::Ready => Ok(I2cSlave::new(self.i2c)),
+            _ => Err(self),
+        }
+    }
+
+    /// Run `transfer`, and again after a back-off each time it loses the arbitration.
+    pub(super) fn retry<F>(&self, transfer: F) -> Result<()>
+    where
+        F: Fn() -> Result<()>,
+    {
+        let (retries, backoff) = match self.retry.as_ref() {
+            Some(retry) => (retry.retries, retry.backoff),
+            None => (0, 0),
+        };
+
+        let mut tries = 0;
+
+        loop {
+            let result = transfer();
+
+            if result.is_ok() || tries >= retries || !arbitration_lost(self.i2c) {
+                return result;
+            }
+
+            unsafe { HAL_Delay(backoff << tries.min(16)) };
+            tries += 1;
+        }
+    }
+}
+
+impl I2cSlave
+{
+    pub fn error(&self) -> I2cError
+    {
+        I2cError::from(unsafe { HAL_I2C_GetError(self.i2c.into()) })
+    }
+
+    /// Take the I2C as a master, the slave is given back while it is addressed.
+    ///
+    /// A listening slave stops listening and the registers it serves are dropped. The I2C is not
+    /// initialized again.
+    pub fn into_master(self) -> core::result::Result<I2cMaster, I2cSlave>
+    {
+        let hi2c: &Hi2c = self.i2c.into();
+
+        // Refused unless listening without a transfer, which is checked by the HAL itself.
+        if unsafe { HAL_I2C_DisableListen_IT(hi2c) }.ok().is_ok() {
+            EventCenter::registers(self.i2c, None);
+        }
+
+        match unsafe { HAL_I2C_GetState(hi2c) } {
+            I2cState::Ready => Ok(I2cMaster::new(self.i2c)),
+            _ => Err(self),
+        }
+    }
+}
+
+/// Whether the last transfer has lost the arbitration. The blocking calls of the HAL do not check it
+/// and rather time out, so the flag of the I2C is read, and cleared, as well.
+fn arbitration_lost(i2c: I2cIdentifies) -> bool
+{
+    let hi2c: &Hi2c = i2c.into();
+
+    unsafe {
+        let registers = hi2c.instance as *mut I2cRegisters;
+        let flagged = core::ptr::read_volatile(core::ptr::addr_of!((*registers).sr1)) & I2C_SR1_ARLO != 0;
+
+        if flagged {
+            core::ptr::write_volatile(core::ptr::addr_of_mut!((*registers).sr1), !I2C_SR1_ARLO);
+        }
+
+        flagged || I2cError::from(HAL_I2C_GetError(hi2c)).arbitration_lost()
+    }
+}
@@ -0,0 +1,211 @@
+use escw_mcu::common::Error;
+use escw_mcu::common::Result;
+
+use crate::hal::i2c::*;
+use crate::hal::system::HAL_RCC_GetPCLK1Freq;
+
+use super::I2cAddress;
+use super::I2cIdentifies;
+use super::I2cMaster;
+use super::I2cSlave;
+
+/// The highest SCL clock of the standard mode, above it the I2C runs in fast mode.
+pub const I2C_STANDARD_MODE_SPEED: u32 = 100_000;
+pub const I2C_FAST_MODE_SPEED: u32 = 400_000;
+
+/// The lowest APB1 clock of the standard mode and of the fast mode, and the highest one the I2C takes.
+const STANDARD_MODE_PCLK1: u32 = 2_000_000;
+const FAST_MODE_PCLK1: u32 = 4_000_000;
+const MAX_PCLK1: u32 = 50_000_000;
+
+/// The ratio of the low to the high period of SCL in fast mode.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum I2cDutyCycle
+{
+    /// 2:1, which reaches 400 kHz from an APB1 clock multiple of 1.2 MHz.
+    Duty2,
+    /// 16:9, which reaches 400 kHz from an APB1 clock multiple of 10 MHz.
+    Duty16By9,
+}
+
+impl Into<u32> for I2cDutyCycle
+{
+    fn into(self) -> u32
+    {
+        match self {
+            Self::Duty2 => I2C_DUTYCYCLE_2,
+            Self::Duty16By9 => I2C_DUTYCYCLE_16_9,
+        }
+    }
+}
+
+pub struct I2cConfig
+{
+    /// The SCL clock in Hz, up to 400 kHz. The real one is the APB1 clock divided by a whole number.
+    pub speed: u32,
+    /// Only used in fast mode.
+    pub duty_cycle: I2cDutyCycle,
+    /// The address the I2C answers as a slave, which also sets the addressing mode of the master.
+    pub own_address: I2cAddress,
+    /// A second 7 bits address the I2C answers as a slave.
+    pub own_address2: Option<u8>,
+    pub general_call: bool,
+    /// Let the I2C hold SCL low as a slave while it is not ready, which the master must tolerate.
+    pub clock_stretching: bool,
+}
+
+impl I2cConfig
+{
+    /// A config with the 2:1 duty cycle, no own address, no general call and clock stretching.
+    pub const fn new(speed: u32) -> Self
+    {
+        I2cConfig {
+            speed,
+            duty_cycle: I2cDutyCycle::Duty2,
+            own_address: I2cAddress::SevenBit(0),
+            own_address2: None,
+            general_call: false,
+            clock_stretching: true,
+        }
+    }
+
+    pub const fn with_duty_cycle(mut self, duty_cycle: I2cDutyCycle) -> Self
+    {
+        self.duty_cycle = duty_cycle;
+        self
+    }
+
+    pub const fn with_own_address(mut self, own_address: I2cAddress) -> Self
+    {
+        self.own_address = own_address;
+        self
+    }
+
+    pub const fn with_own_address2(mut self, own_address2: u8) -> Self
+    {
+        self.own_address2 = Some(own_address2);
+        self
+    }
+
+    pub const fn with_general_call(mut self, general_call: bool) -> Self
+    {
+        self.general_call = general_call;
+        self
+    }
+
+    pub const fn with_clock_stretching(mut self, clock_stretching: bool) -> Self
+    {
+        self.clock_stretching = clock_stretching;
+        self
+    }
+
+    /// Refuse a speed the APB1 clock `pclk1` cannot give.
+    fn check(&self, pclk1: u32) -> Result<()>
+    {
+        let lowest = match self.speed > I2C_STANDARD_MODE_SPEED {
+            true => FAST_MODE_PCLK1,
+            false => STANDARD_MODE_PCLK1,
+        };
+
+        if self.speed == 0 || self.speed > I2C_FAST_MODE_SPEED || !(lowest..=MAX_PCLK1).contains(&pclk1) {
+            return Err(Error::Param);
+        }
+
+        if matches!(self.own_address2, Some(address) if address > 0x7F) {
+            return Err(Error::Param);
+        }
+
+        Ok(())
+    }
+}
+
+impl I2cMaster
+{
+    /// Initialize the I2C again with another speed and other settings, the HAL computes the clock
+    /// dividers from the current APB1 clock.
+    pub fn configure(&self, config: &I2cConfig) -> Result<()>
+    {
+        configure(self.i2c, config)
+    }
+
+    /// The real SCL clock in Hz, from the settings of the I2C and the current APB1 clock.
+    pub fn speed(&self) -> u32
+    {
+        speed(self.i2c)
+    }
+}
+
+impl I2cSlave
+{
+    pub fn configure(&self, config: &I2cConfig) -> Result<()>
+    {
+        configure(self.i2c, config)
+    }
+
+    pub fn speed(&self) -> u32
+    {
+        speed(self.i2c)
+    }
+}
+
+fn configure(i2c: I2cIdentifies, config: &I2cConfig) -> Result<()>
+{
+    config.check(unsafe { HAL_RCC_GetPCLK1Freq() })?;
+
+    let (own_address, addressing_mode) = config.own_address.hal()?;
+
+    unsafe {
+        let hi2c: *mut Hi2c = i2c.into();
+
+        // The initialization resets the I2C, which would cut a running transfer or listen.
+        if !matches!(HAL_I2C_GetState(&*hi2c), I2cState::Ready | I2cState::Reset) {
+            return Err(Error::PeripheralBusy);
+        }
+
+        (*hi2c).init.clock_speed = config.speed;
+        (*hi2c).init.duty_cycle = config.duty_cycle.into();
+        (*hi2c).init.own_address1 = own_address as u32;
+        (*hi2c).init.addressing_mode = addressing_mode;
+        (*hi2c).init.dual_address_mode = match config.own_address2 {
+            Some(_) => I2C_DUALADDRESS_ENABLE,
+            None => I2C_DUALADDRESS_DISABLE,
+        };
+        (*hi2c).init.own_address2 = config.own_address2.map_or(0, |address| (address as u32) << 1);
+        (*hi2c).init.general_call_mode = match config.general_call {
+            true => I2C_GENERALCALL_ENABLE,
+            false => I2C_GENERALCALL_DISABLE,
+        };
+        (*hi2c).init.no_stretch_mode = match config.clock_stretching {
+            true => I2C_NOSTRETCH_DISABLE,
+            false => I2C_NOSTRETCH_ENABLE,
+        };
+
+        HAL_I2C_Init(&*hi2c).into()
+    }
+}
+
+/// The divider is computed as the HAL does: the APB1 clock over twice the speed in standard mode, over
+/// three or 25 times the speed in fast mode, rounded up, and at least 4 in standard mode.
+fn speed(i2c: I2cIdentifies) -> u32
+{
+    let hi2c: &Hi2c = i2c.into();
+    let pclk1 = unsafe { HAL_RCC_GetPCLK1Freq() };
+    let speed = hi2c.init.clock_speed;
+
+    if speed == 0 {
+        return 0;
+    }
+
+    let periods = match (speed > I2C_STANDARD_MODE_SPEED, hi2c.init.duty_cycle) {
+        (false, _) => 2,
+        (true, I2C_DUTYCYCLE_2) => 3,
+        (true, _) => 25,
+    };
+
+    let lowest = match speed > I2C_STANDARD_MODE_SPEED {
+        true => 1,
+        false => 4,
+    };
+
+    pclk1 / (periods * (pclk1.saturating_sub(1) / (periods * speed) + 1).max(lowest))
+}
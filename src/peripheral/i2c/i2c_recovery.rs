@@ -0,0 +1,155 @@
+use core::sync::atomic::AtomicU32;
+use core::sync::atomic::Ordering;
+
+use escw_mcu::common::Error;
+use escw_mcu::common::Result;
+use escw_mcu::peripheral::io::IoDevice;
+use escw_mcu::peripheral::io::IoState;
+
+use crate::hal::i2c::*;
+use crate::hal::io::GPIO_PIN_RESET;
+use crate::hal::io::GPIO_PIN_SET;
+use crate::peripheral::delay::delay_ns;
+use crate::peripheral::io::Io;
+
+use super::I2cIdentifies;
+use super::I2cMaster;
+
+/// Half a period of the clock of a standard mode bus, which any slave follows.
+const HALF_PERIOD_NS: u32 = 5_000;
+
+/// The longest a slave may stretch one pulse of the recovery clock.
+const STRETCH_NS: u32 = 1_000_000;
+
+/// The pins of a bus and when to recover it without being asked.
+pub struct I2cRecovery
+{
+    scl: Io,
+    sda: Io,
+    busy_limit: u32,
+    busy_count: AtomicU32,
+}
+
+impl I2cRecovery
+{
+    /// Count a result of the master, true when the bus has been found busy `busy_limit` times in a row.
+    fn count(&self, result: &Result<()>) -> bool
+    {
+        match result {
+            Err(Error::PeripheralBusy) if self.busy_limit != 0 => {
+                if self.busy_count.fetch_add(1, Ordering::Relaxed) + 1 >= self.busy_limit {
+                    self.busy_count.store(0, Ordering::Relaxed);
+                    return true;
+                }
+
+                false
+            }
+            Err(Error::PeripheralBusy) => false,
+            _ => {
+                self.busy_count.store(0, Ordering::Relaxed);
+                false
+            }
+        }
+    }
+}
+
+impl I2cMaster
+{
+    /// Give the pins of the bus to `recover_bus`, which is also run after `busy_limit` transfers in a row
+    /// have found the bus busy, unless it is 0. The transfer which triggers it still fails.
+    pub fn with_recovery(&mut self, scl: Io, sda: Io, busy_limit: u32)
+    {
+        self.recovery = Some(I2cRecovery {
+            scl,
+            sda,
+            busy_limit,
+            busy_count: AtomicU32::new(0),
+        });
+    }
+
+    /// Free a bus held by a slave reset in the middle of a transfer.
+    ///
+    /// The peripheral is deinitialized and the pins are driven by hand: up to nine clock pulses let the
+    /// slave shift out the rest of its byte until it releases SDA, then a STOP ends its transfer. The
+    /// peripheral is initialized again, which gives the pins back to it. `Error::PeripheralBusy` is
+    /// returned if SDA stays low.
+    pub fn recover_bus(&self) -> Result<()>
+    {
+        match self.recovery.as_ref() {
+            Some(recovery) => recover(self.i2c, recovery.scl, recovery.sda),
+            None => Err(Error::Param),
+        }
+    }
+
+    /// Recover the bus when the result is one busy error too many, and give the result back.
+    pub(super) fn watch(&self, result: Result<()>) -> Result<()>
+    {
+        if let Some(recovery) = self.recovery.as_ref() {
+            if recovery.count(&result) {
+                let _ = recover(self.i2c, recovery.scl, recovery.sda);
+            }
+        }
+
+        result
+    }
+}
+
+fn recover(i2c: I2cIdentifies, scl: Io, sda: Io) -> Result<()>
+{
+    let hi2c: &Hi2c = i2c.into();
+
+    unsafe { HAL_I2C_DeInit(hi2c) }.ok()?;
+
+    scl.init_open_drain();
+    sda.init_open_drain();
+    delay_ns(HALF_PERIOD_NS);
+
+    for _ in 0..9 {
+        if is_high(sda) {
+            break;
+        }
+
+        scl.set_state(IoState::from(GPIO_PIN_RESET));
+        delay_ns(HALF_PERIOD_NS);
+        release_clock(scl);
+    }
+
+    // A STOP is a rise of SDA while SCL is high.
+    scl.set_state(IoState::from(GPIO_PIN_RESET));
+    delay_ns(HALF_PERIOD_NS);
+    sda.set_state(IoState::from(GPIO_PIN_RESET));
+    delay_ns(HALF_PERIOD_NS);
+    release_clock(scl);
+    sda.set_state(IoState::from(GPIO_PIN_SET));
+    delay_ns(HALF_PERIOD_NS);
+
+    let released = is_high(sda);
+
+    unsafe { HAL_I2C_Init(hi2c) }.ok()?;
+
+    match released {
+        true => Ok(()),
+        false => Err(Error::PeripheralBusy),
+    }
+}
+
+/// Let SCL rise, and wait for a slave which stretches it.
+fn release_clock(scl: Io)
+{
+    scl.set_state(IoState::from(GPIO_PIN_SET));
+
+    for _ in 0..STRETCH_NS / HALF_PERIOD_NS {
+        if is_high(scl) {
+            break;
+        }
+
+        delay_ns(HALF_PERIOD_NS);
+    }
+
+    delay_ns(HALF_PERIOD_NS);
+}
+
+fn is_high(io: Io) -> bool
+{
+    Into::<u32>::into(io.state()) == GPIO_PIN_SET
+}
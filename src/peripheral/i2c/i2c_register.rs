@@ -0,0 +1,234 @@
+use escw_mcu::common::Error;
+use escw_mcu::common::Result;
+use escw_mcu::peripheral::i2c::I2cDirection;
+use escw_mcu::peripheral::i2c::I2cEvent;
+
+use crate::hal::i2c::*;
+
+use super::event::EventCenter;
+use super::I2cIdentifies;
+use super::I2cSlave;
+
+/// The most registers a map has, as the register pointer written by the master is one byte.
+const MAP_SIZE: usize = 256;
+
+/// What the master has done to the registers, told after its STOP, with the first register and the count.
+#[derive(Clone, Copy)]
+pub enum I2cRegisterEvent
+{
+    Written(usize, usize),
+    Read(usize, usize),
+}
+
+pub type I2cRegisterEventHandle = fn(I2cRegisterEvent);
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Access
+{
+    Write,
+    Read,
+}
+
+/// The registers of a slave, which the master reads and writes through a register pointer.
+///
+/// A write of the master starts with the register pointer, the next bytes are written from it on.
+/// A read, after a repeated start or in another transfer, goes on from the pointer. The pointer is
+/// incremented after each byte, and wraps around at the end of the map.
+#[derive(Clone, Copy)]
+pub struct I2cRegisterMap
+{
+    registers: *mut u8,
+    size: usize,
+    handle: Option<I2cRegisterEventHandle>,
+    pointer: usize,
+    /// The register pointer, received alone at the start of a write.
+    byte: u8,
+    access: Option<Access>,
+    addressed: bool,
+    start: usize,
+    count: usize,
+}
+
+impl I2cRegisterMap
+{
+    pub fn on_event(&mut self, i2c: I2cIdentifies, event: I2cEvent)
+    {
+        let hi2c: &Hi2c = i2c.into();
+
+        let result = match event {
+            I2cEvent::Awakened((I2cDirection::Rx, _)) => self.begin(hi2c, Access::Write),
+            I2cEvent::Awakened((I2cDirection::Tx, _)) => self.begin(hi2c, Access::Read),
+            I2cEvent::RxCompleted => self.received(hi2c),
+            I2cEvent::TxCompleted => self.transmitted(hi2c),
+            I2cEvent::Error => {
+                self.end(hi2c);
+                Ok(())
+            }
+            _ => Ok(()),
+        };
+
+        if result.is_err() {
+            self.end(hi2c);
+        }
+    }
+
+    /// Tell what the transfer has done, after its STOP, and listen for the next one.
+    pub fn end(&mut self, hi2c: &Hi2c)
+    {
+        // The master ends a read with a NACK, the byte loaded into DR in advance was not sent.
+        if self.access == Some(Access::Read) && self.count > 0 {
+            self.count -= 1;
+            self.pointer = (self.pointer + self.size - 1) % self.size;
+        }
+
+        if let (Some(handle), true) = (self.handle, self.count > 0) {
+            match self.access {
+                Some(Access::Write) => handle(I2cRegisterEvent::Written(self.start, self.count)),
+                Some(Access::Read) => handle(I2cRegisterEvent::Read(self.start, self.count)),
+                None => {}
+            }
+        }
+
+        self.access = None;
+        self.count = 0;
+
+        // Refused while the HAL is still listening, which is all right.
+        unsafe { HAL_I2C_EnableListen_IT(hi2c) };
+    }
+
+    fn begin(&mut self, hi2c: &Hi2c, access: Access) -> Result<()>
+    {
+        // A read after a repeated start ends the write of the register pointer.
+        if let (Some(handle), Some(Access::Write), true) = (self.handle, self.access, self.count > 0) {
+            handle(I2cRegisterEvent::Written(self.start, self.count));
+        }
+
+        self.access = Some(access);
+        self.start = self.pointer;
+        self.count = 0;
+
+        match access {
+            Access::Write => {
+                self.addressed = false;
+
+                unsafe { HAL_I2C_Slave_Seq_Receive_IT(hi2c, core::ptr::addr_of_mut!(self.byte), 1, I2C_FIRST_FRAME).ok() }
+            }
+            Access::Read => unsafe { HAL_I2C_Slave_Seq_Transmit_IT(hi2c, self.register(), 1, I2C_FIRST_FRAME).ok() },
+        }
+    }
+
+    fn received(&mut self, hi2c: &Hi2c) -> Result<()>
+    {
+        match self.addressed {
+            false => {
+                self.addressed = true;
+                self.pointer = self.byte as usize % self.size;
+                self.start = self.pointer;
+            }
+            true => {
+                self.count += 1;
+                self.pointer = (self.pointer + 1) % self.size;
+            }
+        }
+
+        unsafe { HAL_I2C_Slave_Seq_Receive_IT(hi2c, self.register(), 1, I2C_NEXT_FRAME).ok() }
+    }
+
+    fn transmitted(&mut self, hi2c: &Hi2c) -> Result<()>
+    {
+        self.count += 1;
+        self.pointer = (self.pointer + 1) % self.size;
+
+        unsafe { HAL_I2C_Slave_Seq_Transmit_IT(hi2c, self.register(), 1, I2C_NEXT_FRAME).ok() }
+    }
+
+    fn register(&self) -> *const u8
+    {
+        unsafe { self.registers.add(self.pointer) }
+    }
+
+    /// The registers from `start` to `start + len`, none beyond the map.
+    fn range(&self, start: usize, len: usize) -> Result<*mut u8>
+    {
+        match start.checked_add(len) {
+            Some(end) if end <= self.size => Ok(unsafe { self.registers.add(start) }),
+            _ => Err(Error::Param),
+        }
+    }
+}
+
+impl I2cSlave
+{
+    /// Serve `registers` to the master, from the address match to the STOP, until `stop_registers`.
+    ///
+    /// The map has at most 256 registers. The events of the I2C are not sent to its handle meanwhile,
+    /// `handle` is told of the registers written or read by each transfer. The map is then only reached
+    /// through `read_registers` and `write_registers`, a register taken by a running transfer may
+    /// change at any time.
+    pub fn serve_registers(&self, registers: &'static mut [u8], handle: Option<I2cRegisterEventHandle>) -> Result<()>
+    {
+        if registers.is_empty() || registers.len() > MAP_SIZE {
+            return Err(Error::Param);
+        }
+
+        // A listening slave keeps the map it serves, or the events of its plain handle.
+        if !matches!(unsafe { HAL_I2C_GetState(self.i2c.into()) }, I2cState::Ready) {
+            return Err(Error::PeripheralBusy);
+        }
+
+        let map = I2cRegisterMap {
+            registers: registers.as_mut_ptr(),
+            size: registers.len(),
+            handle,
+            pointer: 0,
+            byte: 0,
+            access: None,
+            addressed: false,
+            start: 0,
+            count: 0,
+        };
+
+        EventCenter::registers(self.i2c, Some(map));
+
+        let result = unsafe { HAL_I2C_EnableListen_IT(self.i2c.into()).ok() };
+
+        if result.is_err() {
+            EventCenter::registers(self.i2c, None);
+        }
+
+        result
+    }
+
+    /// Copy the registers from `start` into `data`, as the master has written them.
+    pub fn read_registers(&self, start: usize, data: &mut [u8]) -> Result<()>
+    {
+        let map = EventCenter::register_map(self.i2c).ok_or(Error::Param)?;
+        let registers = map.range(start, data.len())?;
+
+        for (index, byte) in data.iter_mut().enumerate() {
+            *byte = unsafe { core::ptr::read_volatile(registers.add(index)) };
+        }
+
+        Ok(())
+    }
+
+    /// Copy `data` into the registers from `start`, for the master to read them.
+    pub fn write_registers(&self, start: usize, data: &[u8]) -> Result<()>
+    {
+        let map = EventCenter::register_map(self.i2c).ok_or(Error::Param)?;
+        let registers = map.range(start, data.len())?;
+
+        for (index, byte) in data.iter().enumerate() {
+            unsafe { core::ptr::write_volatile(registers.add(index), *byte) };
+        }
+
+        Ok(())
+    }
+
+    pub fn stop_registers(&self) -> Result<()>
+    {
+        EventCenter::registers(self.i2c, None);
+
+        unsafe { HAL_I2C_DisableListen_IT(self.i2c.into()).into() }
+    }
+}
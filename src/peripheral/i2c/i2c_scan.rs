@@ -0,0 +1,174 @@
+use core::ptr::null;
+
+use escw_mcu::common::Error;
+use escw_mcu::common::Result;
+
+use crate::hal::i2c::*;
+
+use super::addressing;
+use super::event::EventCenter;
+use super::i2c_address::I2cAddress;
+use super::I2cIdentifies;
+use super::I2cMaster;
+
+/// The 7 bits addresses not reserved by the specification.
+const FIRST_ADDRESS: u16 = 0x08;
+const LAST_ADDRESS: u16 = 0x77;
+
+const LAST_TEN_BIT_ADDRESS: u16 = 0x3FF;
+
+/// What an asynchronous scan has found.
+#[derive(Clone, Copy)]
+pub enum I2cScanEvent
+{
+    /// An address has been probed, true if a device has acknowledged it.
+    Probed(I2cAddress, bool),
+    /// The scan is over, with the presence map of the 7 bits addresses.
+    Completed(u128),
+}
+
+pub type I2cScanEventHandle = fn(I2cScanEvent);
+
+/// An asynchronous scan, which probes the next address from the end event of the previous probe.
+#[derive(Clone, Copy)]
+pub struct I2cScan
+{
+    handle: I2cScanEventHandle,
+    ten_bit: bool,
+    in_ten_bit: bool,
+    address: u16,
+    presence: u128,
+}
+
+impl I2cScan
+{
+    /// Take the outcome of the probe, and probe the next address, false when the scan is over.
+    pub fn advance(&mut self, i2c: I2cIdentifies, present: bool) -> bool
+    {
+        let mut present = present;
+
+        loop {
+            self.report(present);
+
+            if !self.next(i2c) {
+                let _ = addressing(i2c, I2C_ADDRESSINGMODE_7BIT);
+                (self.handle)(I2cScanEvent::Completed(self.presence));
+
+                return false;
+            }
+
+            // An address which cannot be probed is taken as missing.
+            match self.probe(i2c) {
+                Ok(()) => return true,
+                Err(_) => present = false,
+            }
+        }
+    }
+
+    fn report(&mut self, present: bool)
+    {
+        let address = match self.in_ten_bit {
+            true => I2cAddress::TenBit(self.address),
+            false => I2cAddress::SevenBit(self.address as u8),
+        };
+
+        if present && !self.in_ten_bit {
+            self.presence |= 1 << self.address;
+        }
+
+        (self.handle)(I2cScanEvent::Probed(address, present))
+    }
+
+    fn next(&mut self, i2c: I2cIdentifies) -> bool
+    {
+        match (self.in_ten_bit, self.address) {
+            (false, LAST_ADDRESS) if self.ten_bit => {
+                if addressing(i2c, I2C_ADDRESSINGMODE_10BIT).is_err() {
+                    return false;
+                }
+
+                self.in_ten_bit = true;
+                self.address = 0;
+                true
+            }
+            (false, LAST_ADDRESS) | (true, LAST_TEN_BIT_ADDRESS) => false,
+            _ => {
+                self.address += 1;
+                true
+            }
+        }
+    }
+
+    /// Send the address alone, a device acknowledges it with the end of the transmission.
+    fn probe(&self, i2c: I2cIdentifies) -> Result<()>
+    {
+        let device = match self.in_ten_bit {
+            true => self.address,
+            false => self.address << 1,
+        };
+
+        unsafe { HAL_I2C_Master_Transmit_IT(i2c.into(), device, null(), 0).into() }
+    }
+}
+
+impl I2cMaster
+{
+    /// Probe the 7 bits addresses from 0x08 to 0x77, bit `n` of the map is set if a device answers at `n`.
+    pub fn scan(&self, timeout: u32) -> u128
+    {
+        (FIRST_ADDRESS..=LAST_ADDRESS)
+            .filter(|address| unsafe { HAL_I2C_IsDeviceReady(self.i2c.into(), address << 1, 1, timeout) }.ok().is_ok())
+            .fold(0, |presence, address| presence | 1 << address)
+    }
+
+    /// Probe the 10 bits addresses, bit `n % 32` of `presence[n / 32]` is set if a device answers at `n`.
+    ///
+    /// The I2C is initialized again in 10 bits addressing mode during the scan, then back in 7 bits mode.
+    pub fn scan_ten_bit(&self, presence: &mut [u32; 32], timeout: u32) -> Result<()>
+    {
+        addressing(self.i2c, I2C_ADDRESSINGMODE_10BIT)?;
+
+        for address in 0..=LAST_TEN_BIT_ADDRESS {
+            let present = unsafe { HAL_I2C_Master_Transmit(self.i2c.into(), address, null(), 0, timeout) }.ok().is_ok();
+
+            match present {
+                true => presence[address as usize / 32] |= 1 << (address % 32),
+                false => presence[address as usize / 32] &= !(1 << (address % 32)),
+            }
+        }
+
+        addressing(self.i2c, I2C_ADDRESSINGMODE_7BIT)
+    }
+
+    /// Probe the 7 bits addresses, then the 10 bits ones if `ten_bit`, with interrupts.
+    ///
+    /// Each address probed sends an event to `handle`, and the end of the scan sends `I2cScanEvent::Completed`.
+    /// The events of the I2C are not sent to its handle during the scan.
+    pub fn scan_with_interrupt(&self, ten_bit: bool, handle: I2cScanEventHandle) -> Result<()>
+    {
+        // The running scan is still kept by the event center.
+        if EventCenter::is_scanning(self.i2c) || !matches!(unsafe { HAL_I2C_GetState(self.i2c.into()) }, I2cState::Ready) {
+            return Err(Error::PeripheralBusy);
+        }
+
+        addressing(self.i2c, I2C_ADDRESSINGMODE_7BIT)?;
+
+        let scan = I2cScan {
+            handle,
+            ten_bit,
+            in_ten_bit: false,
+            address: FIRST_ADDRESS,
+            presence: 0,
+        };
+
+        EventCenter::scan(self.i2c, Some(scan));
+
+        let result = scan.probe(self.i2c);
+
+        if result.is_err() {
+            EventCenter::scan(self.i2c, None);
+        }
+
+        result
+    }
+}
@@ -0,0 +1,298 @@
+use escw_mcu::common::Error;
+use escw_mcu::common::Result;
+
+use crate::hal::i2c::*;
+use crate::hal::HalStatus;
+use crate::peripheral::chunk::CHUNK_SIZE;
+
+use super::event::EventCenter;
+use super::i2c_address::I2cAddress;
+use super::i2c_transfer::wait;
+use super::I2cIdentifies;
+use super::I2cMaster;
+
+/// One segment of a transaction with one device.
+///
+/// The segments run one after the other without any STOP: a segment in the same direction as the
+/// previous one goes on with its transfer, a change of direction makes a repeated start.
+pub enum I2cSegment<'a>
+{
+    Write(&'a [u8]),
+    Read(&'a mut [u8]),
+    /// Read a byte count into the first byte of the buffer, then as many bytes after it, plus the given
+    /// number of trailing bytes, as the block reads of SMBus. A count which leaves nothing to read, or which
+    /// does not fit in the buffer, aborts the transaction.
+    BlockRead(&'a mut [u8], usize),
+}
+
+#[derive(Clone, Copy)]
+enum Step
+{
+    Write,
+    Read,
+    Block(usize),
+}
+
+#[derive(Clone, Copy)]
+enum Segments
+{
+    List(*mut I2cSegment<'static>, usize),
+    /// The buffers of `write_read`, which has no list of segments.
+    WriteRead(*const u8, usize, *const u8, usize),
+}
+
+impl Segments
+{
+    fn count(&self) -> usize
+    {
+        match *self {
+            Self::List(_, count) => count,
+            Self::WriteRead(..) => 2,
+        }
+    }
+
+    /// What a segment does, with its buffer and its length.
+    fn get(&self, index: usize) -> (Step, *const u8, usize)
+    {
+        match (*self, index) {
+            (Self::List(segments, _), _) => match unsafe { &*segments.add(index) } {
+                I2cSegment::Write(data) => (Step::Write, data.as_ptr(), data.len()),
+                I2cSegment::Read(data) => (Step::Read, data.as_ptr(), data.len()),
+                I2cSegment::BlockRead(data, extra) => (Step::Block(*extra), data.as_ptr(), data.len()),
+            },
+            (Self::WriteRead(tx, tx_size, _, _), 0) => (Step::Write, tx, tx_size),
+            (Self::WriteRead(_, _, rx, rx_size), _) => (Step::Read, rx, rx_size),
+        }
+    }
+
+    /// Each segment is one frame of the HAL, which cannot be empty.
+    fn check(&self) -> Result<()>
+    {
+        if self.count() == 0 || (0..self.count()).any(|index| !(1..=CHUNK_SIZE).contains(&self.get(index).2)) {
+            return Err(Error::Param);
+        }
+
+        Ok(())
+    }
+}
+
+/// One call of the HAL within a transaction.
+struct Frame
+{
+    read: bool,
+    data: *const u8,
+    size: usize,
+    options: u32,
+}
+
+/// A block read waiting for its count: the buffer, its length, the trailing bytes and whether it is the last segment.
+#[derive(Clone, Copy)]
+struct Block
+{
+    data: *const u8,
+    capacity: usize,
+    extra: usize,
+    last: bool,
+}
+
+/// A transaction with interrupts or DMA, the next segment is started from the end event of the previous one.
+#[derive(Clone, Copy)]
+pub struct I2cChain
+{
+    device: u16,
+    segments: Segments,
+    dma: bool,
+    next: usize,
+    block: Option<Block>,
+    /// Set for a blocking transaction run with interrupts, whose end is taken by the waiting caller.
+    blocking: bool,
+    pub succeeded: Option<bool>,
+}
+
+impl I2cChain
+{
+    fn new(device: u16, segments: Segments, dma: bool) -> Self
+    {
+        I2cChain {
+            device,
+            segments,
+            dma,
+            next: 0,
+            block: None,
+            blocking: false,
+            succeeded: None,
+        }
+    }
+
+    pub fn is_blocking(&self) -> bool
+    {
+        self.blocking
+    }
+
+    /// Start the transaction, it is kept by the event center until its last segment ends.
+    fn start(mut self, i2c: I2cIdentifies) -> Result<()>
+    {
+        self.segments.check()?;
+
+        // The running transaction is still kept by the event center.
+        if !matches!(unsafe { HAL_I2C_GetState(i2c.into()) }, I2cState::Ready) {
+            return Err(Error::PeripheralBusy);
+        }
+
+        let frame = self.frame();
+
+        EventCenter::chain(i2c, Some(self));
+
+        let result = unsafe { self.start_frame(i2c.into(), frame) }.ok();
+
+        if result.is_err() {
+            EventCenter::chain(i2c, None);
+        }
+
+        result
+    }
+
+    /// Start the next segment, or the rest of a block read, from the end event of the previous frame,
+    /// false when the transaction is done.
+    pub fn advance(&mut self, i2c: I2cIdentifies) -> Result<bool>
+    {
+        if let Some(block) = self.block.take() {
+            let size = unsafe { *block.data } as usize + block.extra;
+
+            if size == 0 || size >= block.capacity {
+                unsafe { HAL_I2C_Master_Abort_IT(i2c.into(), self.device) };
+                return Err(Error::Param);
+            }
+
+            let frame = Frame {
+                read: true,
+                data: block.data.wrapping_add(1),
+                size,
+                options: if block.last { I2C_LAST_FRAME } else { I2C_NEXT_FRAME },
+            };
+
+            return unsafe { self.start_frame(i2c.into(), frame) }.ok().map(|_| true);
+        }
+
+        if self.next >= self.segments.count() {
+            return Ok(false);
+        }
+
+        let frame = self.frame();
+
+        unsafe { self.start_frame(i2c.into(), frame) }.ok().map(|_| true)
+    }
+
+    /// The frame of the next segment, only the count of a block read.
+    fn frame(&mut self) -> Frame
+    {
+        let index = self.next;
+        let first = index == 0;
+        let last = index + 1 == self.segments.count();
+        let (step, data, size) = self.segments.get(index);
+
+        self.next += 1;
+
+        let options = match (first, last) {
+            (true, true) => I2C_FIRST_AND_LAST_FRAME,
+            (true, false) => I2C_FIRST_FRAME,
+            (false, false) => I2C_NEXT_FRAME,
+            (false, true) => I2C_LAST_FRAME,
+        };
+
+        match step {
+            Step::Write => Frame { read: false, data, size, options },
+            Step::Read => Frame { read: true, data, size, options },
+            Step::Block(extra) => {
+                self.block = Some(Block { data, capacity: size, extra, last });
+
+                Frame {
+                    read: true,
+                    data,
+                    size: 1,
+                    options: if first { I2C_FIRST_FRAME } else { I2C_NEXT_FRAME },
+                }
+            }
+        }
+    }
+
+    unsafe fn start_frame(&self, hi2c: &Hi2c, frame: Frame) -> HalStatus
+    {
+        let size = frame.size as u16;
+
+        match (frame.read, self.dma) {
+            (false, false) => HAL_I2C_Master_Seq_Transmit_IT(hi2c, self.device, frame.data, size, frame.options),
+            (true, false) => HAL_I2C_Master_Seq_Receive_IT(hi2c, self.device, frame.data, size, frame.options),
+            (false, true) => HAL_I2C_Master_Seq_Transmit_DMA(hi2c, self.device, frame.data, size, frame.options),
+            (true, true) => HAL_I2C_Master_Seq_Receive_DMA(hi2c, self.device, frame.data, size, frame.options),
+        }
+    }
+}
+
+impl I2cMaster
+{
+    /// Write `tx_data` then read `rx_data` after a repeated start, without any STOP between them.
+    ///
+    /// The transaction runs with interrupts and is waited for, `timeout` applies to the whole of it.
+    pub fn write_read(&self, address: I2cAddress, tx_data: &[u8], rx_data: &mut [u8], timeout: u32) -> Result<()>
+    {
+        let segments = Segments::WriteRead(tx_data.as_ptr(), tx_data.len(), rx_data.as_ptr(), rx_data.len());
+
+        self.run_chain(address, segments, timeout)
+    }
+
+    pub fn write_read_with_interrupt(&self, address: I2cAddress, tx_data: &'static [u8], rx_data: &'static mut [u8]) -> Result<()>
+    {
+        let segments = Segments::WriteRead(tx_data.as_ptr(), tx_data.len(), rx_data.as_ptr(), rx_data.len());
+
+        self.start_chain(address, segments, false)
+    }
+
+    pub fn write_read_with_dma(&self, address: I2cAddress, tx_data: &'static [u8], rx_data: &'static mut [u8]) -> Result<()>
+    {
+        let segments = Segments::WriteRead(tx_data.as_ptr(), tx_data.len(), rx_data.as_ptr(), rx_data.len());
+
+        self.start_chain(address, segments, true)
+    }
+
+    /// Run the segments in one transaction, ended by a STOP.
+    ///
+    /// The transaction runs with interrupts and is waited for, `timeout` applies to the whole of it.
+    pub fn transaction(&self, address: I2cAddress, segments: &mut [I2cSegment], timeout: u32) -> Result<()>
+    {
+        let segments = Segments::List(segments.as_mut_ptr().cast(), segments.len());
+
+        self.run_chain(address, segments, timeout)
+    }
+
+    /// Start the segments in one transaction, the end event of the last segment, or an error, ends it.
+    pub fn transaction_with_interrupt(&self, address: I2cAddress, segments: &'static mut [I2cSegment<'static>]) -> Result<()>
+    {
+        self.start_chain(address, Segments::List(segments.as_mut_ptr(), segments.len()), false)
+    }
+
+    pub fn transaction_with_dma(&self, address: I2cAddress, segments: &'static mut [I2cSegment<'static>]) -> Result<()>
+    {
+        self.start_chain(address, Segments::List(segments.as_mut_ptr(), segments.len()), true)
+    }
+
+    fn run_chain(&self, address: I2cAddress, segments: Segments, timeout: u32) -> Result<()>
+    {
+        let device = self.target(address)?;
+
+        self.watch(self.retry(|| {
+            let mut chain = I2cChain::new(device, segments, false);
+            chain.blocking = true;
+            chain.start(self.i2c)?;
+
+            wait(self.i2c, device, timeout)
+        }))
+    }
+
+    fn start_chain(&self, address: I2cAddress, segments: Segments, dma: bool) -> Result<()>
+    {
+        let device = self.target(address)?;
+
+        self.watch(I2cChain::new(device, segments, dma).start(self.i2c))
+    }
+}
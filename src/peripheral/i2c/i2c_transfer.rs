@@ -0,0 +1,189 @@
+use escw_mcu::common::Error;
+use escw_mcu::common::Result;
+
+use crate::hal::i2c::*;
+use crate::hal::system::HAL_GetTick;
+use crate::hal::HalStatus;
+use crate::peripheral::chunk::Chunk;
+use crate::peripheral::chunk::Chunks;
+
+use super::event::EventCenter;
+use super::I2cIdentifies;
+
+/// What a master transfer does, with its device address, and its memory address and address size.
+#[derive(Clone, Copy)]
+pub enum I2cTransferKind
+{
+    Transmit(u16),
+    Receive(u16),
+    MemoryWrite(u16, u16, u16),
+    MemoryRead(u16, u16, u16),
+}
+
+/// An interrupt or DMA master transfer, the chunks after the first one are started by the end events.
+///
+/// The chunks of a transmission or a reception are frames of one sequential transfer, without any
+/// restart or stop between them. The chunks of a memory access are accesses at consecutive addresses.
+#[derive(Clone, Copy)]
+pub struct I2cTransfer
+{
+    kind: I2cTransferKind,
+    dma: bool,
+    chunks: Chunks,
+    /// Set for a blocking transfer run with interrupts, whose end is taken by the waiting caller.
+    blocking: bool,
+    pub succeeded: Option<bool>,
+}
+
+impl I2cTransfer
+{
+    pub fn new(kind: I2cTransferKind, dma: bool, chunks: Chunks) -> Self
+    {
+        I2cTransfer {
+            kind,
+            dma,
+            chunks,
+            blocking: false,
+            succeeded: None,
+        }
+    }
+
+    pub fn is_blocking(&self) -> bool
+    {
+        self.blocking
+    }
+
+    /// Start the transfer, a long one is kept by the event center until its last chunk ends.
+    pub fn start(mut self, i2c: I2cIdentifies) -> Result<()>
+    {
+        // The record of a running transfer is still used by its end events.
+        if !matches!(unsafe { HAL_I2C_GetState(i2c.into()) }, I2cState::Ready) {
+            return Err(Error::PeripheralBusy);
+        }
+
+        let chunk = self.chunks.next();
+        let long = self.chunks.is_long();
+
+        if long {
+            EventCenter::follow(i2c, Some(self));
+        }
+
+        let result = unsafe { self.start_chunk(i2c.into(), chunk, self.chunks.is_done()) }.ok();
+
+        if result.is_err() && long {
+            EventCenter::follow(i2c, None);
+        }
+
+        result
+    }
+
+    /// Start the next chunk from the end event of the previous one, false when the transfer is done.
+    pub fn advance(&mut self, i2c: I2cIdentifies) -> Result<bool>
+    {
+        if self.chunks.is_done() {
+            return Ok(false);
+        }
+
+        let chunk = self.chunks.next();
+
+        unsafe { self.start_chunk(i2c.into(), chunk, self.chunks.is_done()) }.ok().map(|_| true)
+    }
+
+    unsafe fn start_chunk(&self, hi2c: &Hi2c, chunk: Chunk, last: bool) -> HalStatus
+    {
+        let options = match (chunk.offset == 0, last) {
+            (true, true) => I2C_FIRST_AND_LAST_FRAME,
+            (true, false) => I2C_FIRST_FRAME,
+            (false, false) => I2C_NEXT_FRAME,
+            (false, true) => I2C_LAST_FRAME,
+        };
+
+        match (self.kind, self.dma) {
+            (I2cTransferKind::Transmit(device), false) if options == I2C_FIRST_AND_LAST_FRAME => {
+                HAL_I2C_Master_Transmit_IT(hi2c, device, chunk.tx, chunk.size)
+            }
+            (I2cTransferKind::Receive(device), false) if options == I2C_FIRST_AND_LAST_FRAME => {
+                HAL_I2C_Master_Receive_IT(hi2c, device, chunk.rx, chunk.size)
+            }
+            (I2cTransferKind::Transmit(device), true) if options == I2C_FIRST_AND_LAST_FRAME => {
+                HAL_I2C_Master_Transmit_DMA(hi2c, device, chunk.tx, chunk.size)
+            }
+            (I2cTransferKind::Receive(device), true) if options == I2C_FIRST_AND_LAST_FRAME => {
+                HAL_I2C_Master_Receive_DMA(hi2c, device, chunk.rx, chunk.size)
+            }
+            (I2cTransferKind::Transmit(device), false) => HAL_I2C_Master_Seq_Transmit_IT(hi2c, device, chunk.tx, chunk.size, options),
+            (I2cTransferKind::Receive(device), false) => HAL_I2C_Master_Seq_Receive_IT(hi2c, device, chunk.rx, chunk.size, options),
+            (I2cTransferKind::Transmit(device), true) => HAL_I2C_Master_Seq_Transmit_DMA(hi2c, device, chunk.tx, chunk.size, options),
+            (I2cTransferKind::Receive(device), true) => HAL_I2C_Master_Seq_Receive_DMA(hi2c, device, chunk.rx, chunk.size, options),
+            (I2cTransferKind::MemoryWrite(device, address, wide), false) => {
+                HAL_I2C_Mem_Write_IT(hi2c, device, address.wrapping_add(chunk.offset as u16), wide, chunk.tx, chunk.size)
+            }
+            (I2cTransferKind::MemoryRead(device, address, wide), false) => {
+                HAL_I2C_Mem_Read_IT(hi2c, device, address.wrapping_add(chunk.offset as u16), wide, chunk.rx, chunk.size)
+            }
+            (I2cTransferKind::MemoryWrite(device, address, wide), true) => {
+                HAL_I2C_Mem_Write_DMA(hi2c, device, address.wrapping_add(chunk.offset as u16), wide, chunk.tx, chunk.size)
+            }
+            (I2cTransferKind::MemoryRead(device, address, wide), true) => {
+                HAL_I2C_Mem_Read_DMA(hi2c, device, address.wrapping_add(chunk.offset as u16), wide, chunk.rx, chunk.size)
+            }
+        }
+    }
+}
+
+/// Run a blocking master transfer.
+///
+/// A long transmission or reception cannot be split into blocking calls of the HAL without a stop
+/// between them, so it is run with interrupts and waited for, `timeout` applies to the whole of it.
+/// The other transfers are run as blocking calls of the HAL, `timeout` applies to each of them.
+pub fn run(i2c: I2cIdentifies, kind: I2cTransferKind, chunks: Chunks, timeout: u32) -> Result<()>
+{
+    let hi2c: &Hi2c = i2c.into();
+
+    match kind {
+        I2cTransferKind::Transmit(device) | I2cTransferKind::Receive(device) if chunks.is_long() => {
+            let mut transfer = I2cTransfer::new(kind, false, chunks);
+            transfer.blocking = true;
+            transfer.start(i2c)?;
+
+            wait(i2c, device, timeout)
+        }
+        _ => chunks.run(|chunk| unsafe {
+            match kind {
+                I2cTransferKind::Transmit(device) => HAL_I2C_Master_Transmit(hi2c, device, chunk.tx, chunk.size, timeout),
+                I2cTransferKind::Receive(device) => HAL_I2C_Master_Receive(hi2c, device, chunk.rx, chunk.size, timeout),
+                I2cTransferKind::MemoryWrite(device, address, wide) => {
+                    HAL_I2C_Mem_Write(hi2c, device, address.wrapping_add(chunk.offset as u16), wide, chunk.tx, chunk.size, timeout)
+                }
+                I2cTransferKind::MemoryRead(device, address, wide) => {
+                    HAL_I2C_Mem_Read(hi2c, device, address.wrapping_add(chunk.offset as u16), wide, chunk.rx, chunk.size, timeout)
+                }
+            }
+            .ok()
+        }),
+    }
+}
+
+/// Wait for the end of the blocking transfer or transaction kept by the event center, abort it on timeout.
+pub fn wait(i2c: I2cIdentifies, device: u16, timeout: u32) -> Result<()>
+{
+    let start = unsafe { HAL_GetTick() };
+
+    loop {
+        if let Some(succeeded) = EventCenter::outcome(i2c) {
+            EventCenter::forget(i2c);
+
+            return match succeeded {
+                true => Ok(()),
+                false => Err(HalStatus::Error.into()),
+            };
+        }
+
+        if unsafe { HAL_GetTick() }.wrapping_sub(start) >= timeout {
+            EventCenter::forget(i2c);
+            unsafe { HAL_I2C_Master_Abort_IT(i2c.into(), device) };
+
+            return Err(Error::WaitTimeout);
+        }
+    }
+}
@@ -0,0 +1,358 @@
+use core::ptr::null;
+use core::sync::atomic::AtomicBool;
+use core::sync::atomic::Ordering;
+
+use escw_mcu::common::Error;
+use escw_mcu::peripheral::io::IoDevice;
+
+use crate::hal::i2c::*;
+use crate::hal::io::GPIO_PIN_RESET;
+use crate::peripheral::io::Io;
+use crate::peripheral::io::IoPin;
+
+use super::I2cAddress;
+use super::I2cMaster;
+use super::I2cSegment;
+
+/// The timeout of each blocking transfer in ms, the longest t_TIMEOUT after which a device must have
+/// released the clock. The I2C itself ends a transfer once the clock has been held low for 25 ms.
+pub const SMBUS_TIMEOUT: u32 = 35;
+
+/// The most data bytes of a block transfer, as PMBus and SMBus 3.0 allow.
+pub const SMBUS_BLOCK_SIZE: usize = 255;
+
+/// The address the host reads to learn which device has pulled SMBALERT# low.
+pub const SMBUS_ALERT_RESPONSE_ADDRESS: u8 = 0x0C;
+
+pub enum SmbusError
+{
+    /// The block is empty or longer than `SMBUS_BLOCK_SIZE`, or the buffer is too small for it.
+    Param,
+    /// The underlying I2C refused or failed the transfer.
+    I2c(Error),
+    /// The PEC received does not match the transfer.
+    Pec,
+    /// The byte count of a block read does not fit in the buffer.
+    Block,
+    /// A device has held the clock low for longer than t_TIMEOUT, the I2C has ended the transfer.
+    Timeout,
+}
+
+impl From<Error> for SmbusError
+{
+    fn from(value: Error) -> Self
+    {
+        SmbusError::I2c(value)
+    }
+}
+
+pub type SmbusResult<T> = core::result::Result<T, SmbusError>;
+
+/// Told of the 7 bits address of each device which has pulled SMBALERT# low.
+pub type SmbusAlertHandle = fn(u8);
+
+/// The protocols of a SMBus host, and of PMBus which uses them, over an I2C master.
+///
+/// The transfers are built on the I2C master rather than on the SMBus API of the HAL, whose handle would
+/// take the peripheral over from the I2C handle. The I2C runs in its SMBus host mode, so that it detects
+/// a clock held low for 25 ms and ends the transfer, which is told by `SmbusError::Timeout`.
+///
+/// Each transfer is blocking, the reads after a command use a repeated start. With PEC, a CRC-8 of the
+/// whole transfer, addresses included, ends each write and is checked at the end of each read. It is
+/// computed here rather than by the I2C, which cannot check it across a repeated start.
+pub struct Smbus
+{
+    master: I2cMaster,
+    pec: bool,
+    alert: Option<(Io, SmbusAlertHandle)>,
+    alerted: AtomicBool,
+}
+
+impl Smbus
+{
+    pub fn new(master: I2cMaster) -> Self
+    {
+        Smbus {
+            master,
+            pec: false,
+            alert: None,
+            alerted: AtomicBool::new(false),
+        }
+    }
+
+    pub fn master(&self) -> &I2cMaster
+    {
+        &self.master
+    }
+
+    pub fn with_pec(&mut self, pec: bool)
+    {
+        self.pec = pec;
+    }
+
+    /// Watch SMBALERT# on `alert`, whose falling edge interrupt is forwarded to `on_io_event`.
+    pub fn with_alert(&mut self, alert: Io, handle: SmbusAlertHandle)
+    {
+        self.alert = Some((alert, handle));
+    }
+
+    /// Send the address alone with the read/write bit as the only data, which has no PEC.
+    pub fn quick_command(&self, address: u8, read: bool) -> SmbusResult<()>
+    {
+        let device = (address as u16) << 1;
+        let hi2c: &Hi2c = self.master.i2c.into();
+
+        self.timed(|| {
+            let result = match read {
+                true => unsafe { HAL_I2C_Master_Receive(hi2c, device, null(), 0, SMBUS_TIMEOUT).ok() },
+                false => unsafe { HAL_I2C_Master_Transmit(hi2c, device, null(), 0, SMBUS_TIMEOUT).ok() },
+            };
+
+            Ok(self.master.watch(result)?)
+        })
+    }
+
+    pub fn send_byte(&self, address: u8, byte: u8) -> SmbusResult<()>
+    {
+        self.write(address, &[byte])
+    }
+
+    pub fn receive_byte(&self, address: u8) -> SmbusResult<u8>
+    {
+        let mut byte = [0];
+        self.read(address, &[], &mut byte)?;
+
+        Ok(byte[0])
+    }
+
+    pub fn write_byte(&self, address: u8, command: u8, byte: u8) -> SmbusResult<()>
+    {
+        self.write(address, &[command, byte])
+    }
+
+    /// Write a word, low byte first.
+    pub fn write_word(&self, address: u8, command: u8, word: u16) -> SmbusResult<()>
+    {
+        let [low, high] = word.to_le_bytes();
+
+        self.write(address, &[command, low, high])
+    }
+
+    pub fn read_byte(&self, address: u8, command: u8) -> SmbusResult<u8>
+    {
+        let mut byte = [0];
+        self.read(address, &[command], &mut byte)?;
+
+        Ok(byte[0])
+    }
+
+    pub fn read_word(&self, address: u8, command: u8) -> SmbusResult<u16>
+    {
+        let mut word = [0; 2];
+        self.read(address, &[command], &mut word)?;
+
+        Ok(u16::from_le_bytes(word))
+    }
+
+    /// Write a word and read the word answered after a repeated start.
+    pub fn process_call(&self, address: u8, command: u8, word: u16) -> SmbusResult<u16>
+    {
+        let [low, high] = word.to_le_bytes();
+        let mut answer = [0; 2];
+        self.read(address, &[command, low, high], &mut answer)?;
+
+        Ok(u16::from_le_bytes(answer))
+    }
+
+    /// Write the byte count then the block.
+    pub fn block_write(&self, address: u8, command: u8, data: &[u8]) -> SmbusResult<()>
+    {
+        if data.is_empty() || data.len() > SMBUS_BLOCK_SIZE {
+            return Err(SmbusError::Param);
+        }
+
+        let mut frame = [0; SMBUS_BLOCK_SIZE + 2];
+        frame[0] = command;
+        frame[1] = data.len() as u8;
+        frame[2..2 + data.len()].copy_from_slice(data);
+
+        self.write(address, &frame[..2 + data.len()])
+    }
+
+    /// Read a block into `data`, whose length is given by the device, and return it.
+    pub fn block_read(&self, address: u8, command: u8, data: &mut [u8]) -> SmbusResult<usize>
+    {
+        if data.is_empty() {
+            return Err(SmbusError::Param);
+        }
+
+        // The byte count, the block and the PEC.
+        let mut frame = [0; SMBUS_BLOCK_SIZE + 2];
+        let extra = self.pec as usize;
+        let size = 1 + data.len().min(SMBUS_BLOCK_SIZE) + extra;
+
+        let result = self.timed(|| {
+            Ok(self.master.transaction(
+                I2cAddress::SevenBit(address),
+                &mut [I2cSegment::Write(&[command]), I2cSegment::BlockRead(&mut frame[..size], extra)],
+                SMBUS_TIMEOUT,
+            )?)
+        });
+
+        let count = frame[0] as usize;
+
+        match result {
+            Err(SmbusError::I2c(_)) if count > data.len() => return Err(SmbusError::Block),
+            Err(error) => return Err(error),
+            Ok(()) => {}
+        }
+
+        if self.pec {
+            let crc = crc8(crc8(0, &[address << 1, command, address << 1 | 1]), &frame[..1 + count]);
+
+            if crc != frame[1 + count] {
+                return Err(SmbusError::Pec);
+            }
+        }
+
+        data[..count].copy_from_slice(&frame[1..1 + count]);
+
+        Ok(count)
+    }
+
+    /// Forward the IO events here from the handle given to `Io::with_event`, the other pins are ignored.
+    pub fn on_io_event(&self, pin: IoPin)
+    {
+        if let Some((alert, _)) = self.alert {
+            if Into::<u16>::into(pin) == Into::<u16>::into(alert.pin()) {
+                self.alerted.store(true, Ordering::Release);
+            }
+        }
+    }
+
+    /// After SMBALERT# has fallen, read the alert response address until SMBALERT# is released, and tell
+    /// the handle of each device which answers. Call it from the main loop.
+    pub fn poll(&self) -> SmbusResult<()>
+    {
+        let (alert, handle) = match self.alert {
+            Some(alert) => alert,
+            None => return Ok(()),
+        };
+
+        if !self.alerted.swap(false, Ordering::Acquire) {
+            return Ok(());
+        }
+
+        // Each read is won by the device of lowest address, which then releases the line; no more
+        // reads than addresses, for a device which would not.
+        for _ in 0..128 {
+            if Into::<u32>::into(alert.state()) != GPIO_PIN_RESET {
+                break;
+            }
+
+            handle(self.receive_byte(SMBUS_ALERT_RESPONSE_ADDRESS)? >> 1);
+        }
+
+        Ok(())
+    }
+
+    fn write(&self, address: u8, bytes: &[u8]) -> SmbusResult<()>
+    {
+        let mut frame = [0; SMBUS_BLOCK_SIZE + 3];
+        frame[..bytes.len()].copy_from_slice(bytes);
+
+        let size = match self.pec {
+            true => {
+                frame[bytes.len()] = crc8(crc8(0, &[address << 1]), bytes);
+                bytes.len() + 1
+            }
+            false => bytes.len(),
+        };
+
+        self.timed(|| Ok(self.master.write(I2cAddress::SevenBit(address), &frame[..size], SMBUS_TIMEOUT)?))
+    }
+
+    /// Read `data` alone, or after writing `command` and a repeated start, and check its PEC.
+    fn read(&self, address: u8, command: &[u8], data: &mut [u8]) -> SmbusResult<()>
+    {
+        let mut frame = [0; 3];
+        let size = data.len() + self.pec as usize;
+        let target = I2cAddress::SevenBit(address);
+
+        self.timed(|| match command.is_empty() {
+            true => Ok(self.master.read(target, &mut frame[..size], SMBUS_TIMEOUT)?),
+            false => Ok(self.master.write_read(target, command, &mut frame[..size], SMBUS_TIMEOUT)?),
+        })?;
+
+        if self.pec {
+            let crc = match command.is_empty() {
+                true => 0,
+                false => crc8(crc8(0, &[address << 1]), command),
+            };
+
+            if crc8(crc8(crc, &[address << 1 | 1]), &frame[..data.len()]) != frame[data.len()] {
+                return Err(SmbusError::Pec);
+            }
+        }
+
+        data.copy_from_slice(&frame[..data.len()]);
+
+        Ok(())
+    }
+
+    /// Run `transfer` with the I2C in SMBus host mode, which an initialization of the I2C clears, and
+    /// tell a failure after a clock held low as `SmbusError::Timeout`.
+    fn timed<T, F>(&self, transfer: F) -> SmbusResult<T>
+    where
+        F: FnOnce() -> SmbusResult<T>,
+    {
+        let hi2c: &Hi2c = self.master.i2c.into();
+        let registers = hi2c.instance as *mut I2cRegisters;
+
+        unsafe {
+            let cr1 = core::ptr::read_volatile(core::ptr::addr_of!((*registers).cr1));
+
+            if cr1 & (I2C_CR1_SMBUS | I2C_CR1_SMBTYPE) != I2C_CR1_SMBUS | I2C_CR1_SMBTYPE {
+                core::ptr::write_volatile(core::ptr::addr_of_mut!((*registers).cr1), cr1 | I2C_CR1_SMBUS | I2C_CR1_SMBTYPE);
+            }
+        }
+
+        let result = transfer();
+
+        if result.is_ok() {
+            return result;
+        }
+
+        unsafe {
+            let timeout = core::ptr::read_volatile(core::ptr::addr_of!((*registers).sr1)) & I2C_SR1_TIMEOUT != 0;
+
+            if timeout {
+                core::ptr::write_volatile(core::ptr::addr_of_mut!((*registers).sr1), !I2C_SR1_TIMEOUT);
+                return Err(SmbusError::Timeout);
+            }
+        }
+
+        result
+    }
+}
+
+/// CRC-8 of the PEC, polynomial 0x07 with initial value 0, going on from `crc`.
+pub fn crc8(crc: u8, data: &[u8]) -> u8
+{
+    let mut crc = crc;
+
+    for byte in data {
+        crc ^= *byte;
+
+        for _ in 0..8 {
+            if crc & 0x80 != 0 {
+                crc = (crc << 1) ^ 0x07;
+            }
+            else {
+                crc <<= 1;
+            }
+        }
+    }
+
+    crc
+}
@@ -0,0 +1,524 @@
+use escw_mcu::common::Error;
+use escw_mcu::common::Result;
+
+use crate::hal::i2s::*;
+use crate::hal::spi::SpiRegisters;
+use crate::hal::system::HAL_RCCEx_GetPeriphCLKFreq;
+use crate::hal::system::RCC_PERIPHCLK_I2S;
+use crate::peripheral::chunk::CHUNK_SIZE;
+
+/// The SPI blocks which can run as I2S, their I2SxEXT blocks give the second direction of a full duplex stream.
+#[derive(Clone, Copy)]
+pub enum I2sIdentifies
+{
+    #[cfg(feature = "i2s2")]
+    I2s2,
+    #[cfg(feature = "i2s3")]
+    I2s3,
+}
+
+impl I2sIdentifies
+{
+    pub const fn count() -> usize
+    {
+        2
+    }
+}
+
+impl Into<*mut Hi2s> for I2sIdentifies
+{
+    fn into(self) -> *mut Hi2s
+    {
+        match self {
+            #[cfg(feature = "i2s2")]
+            Self::I2s2 => core::ptr::addr_of_mut!(hi2s2),
+            #[cfg(feature = "i2s3")]
+            Self::I2s3 => core::ptr::addr_of_mut!(hi2s3),
+        }
+    }
+}
+
+impl Into<&Hi2s> for I2sIdentifies
+{
+    fn into(self) -> &'static Hi2s
+    {
+        unsafe { &*Into::<*mut Hi2s>::into(self) }
+    }
+}
+
+impl TryInto<I2sIdentifies> for &Hi2s
+{
+    type Error = Error;
+
+    fn try_into(self) -> core::result::Result<I2sIdentifies, Self::Error>
+    {
+        match self.instance {
+            #[cfg(feature = "i2s2")]
+            crate::memory::SPI2_BASE => Ok(I2sIdentifies::I2s2),
+            #[cfg(feature = "i2s3")]
+            crate::memory::SPI3_BASE => Ok(I2sIdentifies::I2s3),
+            _ => Err(Error::Param),
+        }
+    }
+}
+
+/// The direction of the main block, the I2SxEXT block takes the other one in full duplex.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum I2sMode
+{
+    MasterTransmit,
+    MasterReceive,
+}
+
+impl Into<u32> for I2sMode
+{
+    fn into(self) -> u32
+    {
+        match self {
+            Self::MasterTransmit => I2S_MODE_MASTER_TX,
+            Self::MasterReceive => I2S_MODE_MASTER_RX,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum I2sStandard
+{
+    Philips,
+    Msb,
+    Lsb,
+    PcmShort,
+    PcmLong,
+}
+
+impl Into<u32> for I2sStandard
+{
+    fn into(self) -> u32
+    {
+        match self {
+            Self::Philips => I2S_STANDARD_PHILIPS,
+            Self::Msb => I2S_STANDARD_MSB,
+            Self::Lsb => I2S_STANDARD_LSB,
+            Self::PcmShort => I2S_STANDARD_PCM_SHORT,
+            Self::PcmLong => I2S_STANDARD_PCM_LONG,
+        }
+    }
+}
+
+/// The size of the data, and of the channel when it differs.
+///
+/// The samples of 24 and 32 bits are two halfwords each in the buffers, the most significant one first,
+/// a sample of 24 bits is aligned to the most significant bit.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum I2sDataFormat
+{
+    Bits16,
+    /// 16 bits data in 32 bits channels.
+    Bits16Extended,
+    Bits24,
+    Bits32,
+}
+
+impl I2sDataFormat
+{
+    /// The halfwords of one sample in the buffers.
+    pub const fn halfwords(&self) -> usize
+    {
+        match self {
+            Self::Bits16 | Self::Bits16Extended => 1,
+            Self::Bits24 | Self::Bits32 => 2,
+        }
+    }
+}
+
+impl Into<u32> for I2sDataFormat
+{
+    fn into(self) -> u32
+    {
+        match self {
+            Self::Bits16 => I2S_DATAFORMAT_16B,
+            Self::Bits16Extended => I2S_DATAFORMAT_16B_EXTENDED,
+            Self::Bits24 => I2S_DATAFORMAT_24B,
+            Self::Bits32 => I2S_DATAFORMAT_32B,
+        }
+    }
+}
+
+impl TryFrom<u32> for I2sDataFormat
+{
+    type Error = Error;
+
+    /// The format set in the initialization of the HAL.
+    fn try_from(value: u32) -> core::result::Result<Self, Self::Error>
+    {
+        match value {
+            I2S_DATAFORMAT_16B => Ok(Self::Bits16),
+            I2S_DATAFORMAT_16B_EXTENDED => Ok(Self::Bits16Extended),
+            I2S_DATAFORMAT_24B => Ok(Self::Bits24),
+            I2S_DATAFORMAT_32B => Ok(Self::Bits32),
+            _ => Err(Error::Param),
+        }
+    }
+}
+
+/// The format of the stream, applied by `I2s::configure`, the clock source and polarity of the HAL are kept.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct I2sConfig
+{
+    pub mode: I2sMode,
+    pub standard: I2sStandard,
+    pub data_format: I2sDataFormat,
+    /// The sample rate in Hz, from 8000 to 192000, the real one depends on the I2S clock.
+    pub sample_rate: u32,
+    pub mclk_output: bool,
+    pub full_duplex: bool,
+}
+
+impl I2sConfig
+{
+    /// A config of the Philips standard with 16 bits data, without master clock output.
+    pub const fn new(mode: I2sMode, sample_rate: u32) -> Self
+    {
+        I2sConfig {
+            mode,
+            standard: I2sStandard::Philips,
+            data_format: I2sDataFormat::Bits16,
+            sample_rate,
+            mclk_output: false,
+            full_duplex: false,
+        }
+    }
+
+    pub const fn with_standard(mut self, standard: I2sStandard) -> Self
+    {
+        self.standard = standard;
+        self
+    }
+
+    pub const fn with_data_format(mut self, data_format: I2sDataFormat) -> Self
+    {
+        self.data_format = data_format;
+        self
+    }
+
+    pub const fn with_mclk_output(mut self) -> Self
+    {
+        self.mclk_output = true;
+        self
+    }
+
+    /// Run the I2SxEXT block in the other direction, for `I2s::transmit_receive`.
+    pub const fn with_full_duplex(mut self) -> Self
+    {
+        self.full_duplex = true;
+        self
+    }
+}
+
+/// What happened to a stream, the halves are the halves of the buffers given to `I2s`.
+#[derive(Clone, Copy)]
+pub enum I2sEvent
+{
+    /// The first half has been sent, it can be filled again.
+    TxHalf,
+    /// The second half has been sent, it can be filled again.
+    TxCompleted,
+    /// The first half has been received, it can be read.
+    RxHalf,
+    /// The second half has been received, it can be read.
+    RxCompleted,
+    /// Both of the above, in full duplex.
+    TxRxHalf,
+    TxRxCompleted,
+    Error(I2sError),
+}
+
+pub type I2sEventHandle = fn(I2sEvent);
+
+/// The error bits reported by the HAL when an error interrupt occurs.
+#[derive(Clone, Copy)]
+pub struct I2sError(u32);
+
+impl I2sError
+{
+    pub fn overrun(&self) -> bool
+    {
+        self.0 & HAL_I2S_ERROR_OVR != 0
+    }
+
+    pub fn underrun(&self) -> bool
+    {
+        self.0 & HAL_I2S_ERROR_UDR != 0
+    }
+
+    pub fn dma(&self) -> bool
+    {
+        self.0 & HAL_I2S_ERROR_DMA != 0
+    }
+
+    pub fn prescaler(&self) -> bool
+    {
+        self.0 & HAL_I2S_ERROR_PRESCALER != 0
+    }
+}
+
+impl From<u32> for I2sError
+{
+    fn from(value: u32) -> Self
+    {
+        I2sError(value)
+    }
+}
+
+impl Into<u32> for I2sError
+{
+    fn into(self) -> u32
+    {
+        self.0
+    }
+}
+
+/// An I2S master streaming with circular DMA.
+///
+/// The DMA streams of the I2S must be configured in circular mode: each buffer is used as two halves,
+/// one is filled or read by the program while the DMA goes through the other one, and the events tell
+/// which half has just been done.
+pub struct I2s
+{
+    i2s: I2sIdentifies,
+}
+
+impl I2s
+{
+    pub fn new(i2s: I2sIdentifies) -> Self
+    {
+        I2s { i2s }
+    }
+
+    pub fn with_event(&mut self, handle: I2sEventHandle)
+    {
+        event::EventCenter::set(self.i2s, handle)
+    }
+
+    /// Initialize the I2S again with another format.
+    pub fn configure(&self, config: &I2sConfig) -> Result<()>
+    {
+        if !(I2S_AUDIOFREQ_8K..=I2S_AUDIOFREQ_192K).contains(&config.sample_rate) {
+            return Err(Error::Param);
+        }
+
+        unsafe {
+            let hi2s: *mut Hi2s = self.i2s.into();
+
+            (*hi2s).init.mode = config.mode.into();
+            (*hi2s).init.standard = config.standard.into();
+            (*hi2s).init.data_format = config.data_format.into();
+            (*hi2s).init.audio_freq = config.sample_rate;
+            (*hi2s).init.mclk_output = match config.mclk_output {
+                true => I2S_MCLKOUTPUT_ENABLE,
+                false => I2S_MCLKOUTPUT_DISABLE,
+            };
+            (*hi2s).init.full_duplex_mode = match config.full_duplex {
+                true => I2S_FULLDUPLEXMODE_ENABLE,
+                false => I2S_FULLDUPLEXMODE_DISABLE,
+            };
+
+            HAL_I2S_Init(&*hi2s).into()
+        }
+    }
+
+    /// The real sample rate in Hz, given by the prescaler the HAL has chosen from the I2S clock.
+    pub fn sample_rate(&self) -> u32
+    {
+        let hi2s: &Hi2s = self.i2s.into();
+
+        let (config, prescaler) = unsafe {
+            let registers = hi2s.instance as *const SpiRegisters;
+
+            (
+                core::ptr::read_volatile(core::ptr::addr_of!((*registers).i2scfgr)),
+                core::ptr::read_volatile(core::ptr::addr_of!((*registers).i2spr)),
+            )
+        };
+
+        let divider = (prescaler & SPI_I2SPR_I2SDIV) * 2 + ((prescaler & SPI_I2SPR_ODD) != 0) as u32;
+
+        if divider == 0 {
+            return 0;
+        }
+
+        // The master clock is 256 times the sample rate, without it the bit clock is two channels per sample.
+        let bits = match (prescaler & SPI_I2SPR_MCKOE != 0, config & SPI_I2SCFGR_CHLEN != 0) {
+            (true, _) => 256,
+            (false, true) => 64,
+            (false, false) => 32,
+        };
+
+        unsafe { HAL_RCCEx_GetPeriphCLKFreq(RCC_PERIPHCLK_I2S) / (bits * divider) }
+    }
+
+    /// Send `data` endlessly, until the stream is stopped.
+    pub fn transmit(&self, data: &'static mut [u16]) -> Result<()>
+    {
+        let size = self.size(I2S_MODE_MASTER_TX, data.len())?;
+
+        unsafe { HAL_I2S_Transmit_DMA(self.i2s.into(), data.as_ptr(), size).into() }
+    }
+
+    /// Receive into `data` endlessly, until the stream is stopped.
+    pub fn receive(&self, data: &'static mut [u16]) -> Result<()>
+    {
+        let size = self.size(I2S_MODE_MASTER_RX, data.len())?;
+
+        unsafe { HAL_I2S_Receive_DMA(self.i2s.into(), data.as_ptr(), size).into() }
+    }
+
+    /// Send `tx_data` and receive into `rx_data` endlessly, with the I2SxEXT block, in full duplex mode.
+    pub fn transmit_receive(&self, tx_data: &'static mut [u16], rx_data: &'static mut [u16]) -> Result<()>
+    {
+        let hi2s: &Hi2s = self.i2s.into();
+
+        if hi2s.init.full_duplex_mode != I2S_FULLDUPLEXMODE_ENABLE || tx_data.len() != rx_data.len() {
+            return Err(Error::Param);
+        }
+
+        let size = self.size(hi2s.init.mode, tx_data.len())?;
+
+        unsafe { HAL_I2SEx_TransmitReceive_DMA(hi2s, tx_data.as_ptr(), rx_data.as_ptr(), size).into() }
+    }
+
+    pub fn pause(&self) -> Result<()>
+    {
+        unsafe { HAL_I2S_DMAPause(self.i2s.into()).into() }
+    }
+
+    pub fn resume(&self) -> Result<()>
+    {
+        unsafe { HAL_I2S_DMAResume(self.i2s.into()).into() }
+    }
+
+    pub fn stop(&self) -> Result<()>
+    {
+        unsafe { HAL_I2S_DMAStop(self.i2s.into()).into() }
+    }
+
+    /// The error of the stream, also given by `I2sEvent::Error`.
+    pub fn error(&self) -> I2sError
+    {
+        I2sError::from(unsafe { HAL_I2S_GetError(self.i2s.into()) })
+    }
+
+    /// The sample count the HAL takes for a buffer of `len` halfwords, which must hold two halves of
+    /// whole samples, in the direction of the main block.
+    fn size(&self, mode: u32, len: usize) -> Result<u16>
+    {
+        let hi2s: &Hi2s = self.i2s.into();
+        let halfwords = I2sDataFormat::try_from(hi2s.init.data_format)?.halfwords();
+
+        if hi2s.init.mode != mode || len == 0 || len > CHUNK_SIZE || !len.is_multiple_of(halfwords * 2) {
+            return Err(Error::Param);
+        }
+
+        Ok((len / halfwords) as u16)
+    }
+}
+
+mod event
+{
+    use crate::hal::i2s::*;
+
+    use super::I2sEvent;
+    use super::I2sEventHandle;
+    use super::I2sIdentifies;
+
+    static mut EVENT_CENTER: EventCenter = EventCenter::new();
+
+    pub struct EventCenter
+    {
+        handle: [Option<I2sEventHandle>; I2sIdentifies::count()],
+    }
+
+    impl EventCenter
+    {
+        const fn new() -> Self
+        {
+            EventCenter {
+                handle: [None; I2sIdentifies::count()],
+            }
+        }
+
+        pub fn set(i2s: I2sIdentifies, invoke: I2sEventHandle)
+        {
+            unsafe {
+                EVENT_CENTER.handle[i2s as usize] = Some(invoke);
+            }
+        }
+
+        pub fn invoke(i2s: I2sIdentifies, event: I2sEvent)
+        {
+            unsafe {
+                if let Some(invoke) = EVENT_CENTER.handle[i2s as usize].as_ref() {
+                    invoke(event);
+                }
+            }
+        }
+    }
+
+    #[no_mangle]
+    pub extern "C" fn HAL_I2S_TxHalfCpltCallback(hi2s: &Hi2s)
+    {
+        if let Some(i2s) = hi2s.try_into().ok() {
+            EventCenter::invoke(i2s, I2sEvent::TxHalf);
+        }
+    }
+
+    #[no_mangle]
+    pub extern "C" fn HAL_I2S_TxCpltCallback(hi2s: &Hi2s)
+    {
+        if let Some(i2s) = hi2s.try_into().ok() {
+            EventCenter::invoke(i2s, I2sEvent::TxCompleted);
+        }
+    }
+
+    #[no_mangle]
+    pub extern "C" fn HAL_I2S_RxHalfCpltCallback(hi2s: &Hi2s)
+    {
+        if let Some(i2s) = hi2s.try_into().ok() {
+            EventCenter::invoke(i2s, I2sEvent::RxHalf);
+        }
+    }
+
+    #[no_mangle]
+    pub extern "C" fn HAL_I2S_RxCpltCallback(hi2s: &Hi2s)
+    {
+        if let Some(i2s) = hi2s.try_into().ok() {
+            EventCenter::invoke(i2s, I2sEvent::RxCompleted);
+        }
+    }
+
+    #[no_mangle]
+    pub extern "C" fn HAL_I2SEx_TxRxHalfCpltCallback(hi2s: &Hi2s)
+    {
+        if let Some(i2s) = hi2s.try_into().ok() {
+            EventCenter::invoke(i2s, I2sEvent::TxRxHalf);
+        }
+    }
+
+    #[no_mangle]
+    pub extern "C" fn HAL_I2SEx_TxRxCpltCallback(hi2s: &Hi2s)
+    {
+        if let Some(i2s) = hi2s.try_into().ok() {
+            EventCenter::invoke(i2s, I2sEvent::TxRxCompleted);
+        }
+    }
+
+    #[no_mangle]
+    pub extern "C" fn HAL_I2S_ErrorCallback(hi2s: &Hi2s)
+    {
+        if let Some(i2s) = hi2s.try_into().ok() {
+            let error = unsafe { HAL_I2S_GetError(hi2s) };
+
+            EventCenter::invoke(i2s, I2sEvent::Error(error.into()));
+        }
+    }
+}
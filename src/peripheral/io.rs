@@ -9,6 +9,7 @@ use escw_mcu::peripheral::io::{IoDevice, IoState};
 
 static mut EVENT_HANDLE: Option<fn(IoPin)> = None;
 
+#[derive(Clone, Copy)]
 pub struct Io
 {
     port: IoPort,
@@ -21,6 +22,36 @@ impl Io
     {
         Io { port, pin }
     }
+
+    pub const fn port(&self) -> IoPort
+    {
+        self.port
+    }
+
+    pub const fn pin(&self) -> IoPin
+    {
+        self.pin
+    }
+
+    /// Take the pin over as a released open drain output, to drive a bus by hand.
+    #[cfg(any(feature = "i2c1", feature = "i2c2", feature = "i2c3"))]
+    pub(crate) fn init_open_drain(&self)
+    {
+        use crate::hal::io::{GpioInit, HAL_GPIO_Init, GPIO_MODE_OUTPUT_OD, GPIO_PIN_SET, GPIO_PULLUP, GPIO_SPEED_FREQ_LOW};
+
+        let init = GpioInit {
+            pin: Into::<u16>::into(self.pin) as u32,
+            mode: GPIO_MODE_OUTPUT_OD,
+            pull: GPIO_PULLUP,
+            speed: GPIO_SPEED_FREQ_LOW,
+            alternate: 0,
+        };
+
+        unsafe {
+            HAL_GPIO_WritePin(self.port.into(), self.pin.into(), GPIO_PIN_SET);
+            HAL_GPIO_Init(self.port.into(), &init);
+        }
+    }
 }
 
 impl IoDevice for Io
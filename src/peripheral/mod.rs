@@ -1,12 +1,26 @@
 pub mod io;
 pub mod uart;
 
+mod chunk;
+
+#[cfg(any(feature = "adc1", feature = "adc2", feature = "adc3"))]
+pub mod adc;
+
+#[cfg(any(feature = "i2c1", feature = "i2c2", feature = "i2c3", feature = "spi1", feature = "spi2", feature = "spi3", feature = "spi4", feature = "spi5", feature = "spi6"))]
+mod delay;
+
 #[cfg(any(feature = "i2c1", feature = "i2c2", feature = "i2c3"))]
 pub mod i2c;
 
+#[cfg(any(feature = "i2s2", feature = "i2s3"))]
+pub mod i2s;
+
 #[cfg(any(feature = "spi1", feature = "spi2", feature = "spi3", feature = "spi4", feature = "spi5", feature = "spi6",))]
 pub mod spi;
 
+#[cfg(any(feature = "tim1", feature = "tim2", feature = "tim3", feature = "tim4", feature = "tim5", feature = "tim6", feature = "tim7", feature = "tim8", feature = "tim9", feature = "tim10", feature = "tim11", feature = "tim12", feature = "tim13", feature = "tim14"))]
+pub mod timer;
+
 #[cfg(feature = "iwdg")]
 pub mod iwdg;
 
@@ -1,9 +1,30 @@
+mod spi_bus;
+mod spi_slave;
+mod spi_transaction;
+mod spi_transfer;
+
+pub use spi_bus::SpiBus;
+pub use spi_bus::SpiDeviceHandle;
+pub use spi_slave::SpiSlave;
+pub use spi_slave::SpiSlaveErrorCount;
+pub use spi_transaction::SpiOperation;
+
+use core::ptr::null;
+
 use escw_mcu::common::Error;
 use escw_mcu::common::Result;
 use escw_mcu::peripheral::spi::SpiDevice;
 use escw_mcu::peripheral::spi::SpiEventHandle;
 
 use crate::hal::spi::*;
+use crate::hal::system::HAL_RCC_GetPCLK1Freq;
+use crate::hal::system::HAL_RCC_GetPCLK2Freq;
+use crate::peripheral::chunk::Chunks;
+use crate::peripheral::chunk::CHUNK_SIZE;
+use crate::peripheral::delay::delay_ns;
+
+use spi_transfer::SpiDirection;
+use spi_transfer::SpiTransfer;
 
 #[derive(Clone, Copy)]
 pub enum SpiIdentifies
@@ -38,26 +59,32 @@ impl Into<usize> for SpiIdentifies
     }
 }
 
+impl Into<*mut Hspi> for SpiIdentifies
+{
+    fn into(self) -> *mut Hspi
+    {
+        match self {
+            #[cfg(feature = "spi1")]
+            Self::Spi1 => core::ptr::addr_of_mut!(hspi1),
+            #[cfg(feature = "spi2")]
+            Self::Spi2 => core::ptr::addr_of_mut!(hspi2),
+            #[cfg(feature = "spi3")]
+            Self::Spi3 => core::ptr::addr_of_mut!(hspi3),
+            #[cfg(feature = "spi4")]
+            Self::Spi4 => core::ptr::addr_of_mut!(hspi4),
+            #[cfg(feature = "spi5")]
+            Self::Spi5 => core::ptr::addr_of_mut!(hspi5),
+            #[cfg(feature = "spi6")]
+            Self::Spi6 => core::ptr::addr_of_mut!(hspi6),
+        }
+    }
+}
+
 impl Into<&Hspi> for SpiIdentifies
 {
     fn into(self) -> &'static Hspi
     {
-        unsafe {
-            match self {
-                #[cfg(feature = "spi1")]
-                Self::Spi1 => &hspi1,
-                #[cfg(feature = "spi2")]
-                Self::Spi2 => &hspi2,
-                #[cfg(feature = "spi3")]
-                Self::Spi3 => &hspi3,
-                #[cfg(feature = "spi4")]
-                Self::Spi4 => &hspi4,
-                #[cfg(feature = "spi5")]
-                Self::Spi5 => &hspi5,
-                #[cfg(feature = "spi6")]
-                Self::Spi6 => &hspi6,
-            }
-        }
+        unsafe { &*Into::<*mut Hspi>::into(self) }
     }
 }
 
@@ -85,6 +112,244 @@ impl TryInto<SpiIdentifies> for &Hspi
     }
 }
 
+/// The clock polarity and phase, as CPOL/CPHA mode numbers.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum SpiMode
+{
+    Mode0,
+    Mode1,
+    Mode2,
+    Mode3,
+}
+
+impl SpiMode
+{
+    fn polarity(&self) -> u32
+    {
+        match self {
+            Self::Mode0 | Self::Mode1 => SPI_POLARITY_LOW,
+            Self::Mode2 | Self::Mode3 => SPI_POLARITY_HIGH,
+        }
+    }
+
+    fn phase(&self) -> u32
+    {
+        match self {
+            Self::Mode0 | Self::Mode2 => SPI_PHASE_1EDGE,
+            Self::Mode1 | Self::Mode3 => SPI_PHASE_2EDGE,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum SpiBitOrder
+{
+    MsbFirst,
+    LsbFirst,
+}
+
+impl Into<u32> for SpiBitOrder
+{
+    fn into(self) -> u32
+    {
+        match self {
+            Self::MsbFirst => SPI_FIRSTBIT_MSB,
+            Self::LsbFirst => SPI_FIRSTBIT_LSB,
+        }
+    }
+}
+
+/// The size of one frame, the buffers of 16 bits frames are given as `&[u16]`.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum SpiFrameSize
+{
+    Bits8,
+    Bits16,
+}
+
+impl Into<u32> for SpiFrameSize
+{
+    fn into(self) -> u32
+    {
+        match self {
+            Self::Bits8 => SPI_DATASIZE_8BIT,
+            Self::Bits16 => SPI_DATASIZE_16BIT,
+        }
+    }
+}
+
+/// The divider of the APB clock which gives the SPI clock.
+#[repr(u32)]
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum SpiPrescaler
+{
+    Div2 = SPI_BAUDRATEPRESCALER_2,
+    Div4 = SPI_BAUDRATEPRESCALER_4,
+    Div8 = SPI_BAUDRATEPRESCALER_8,
+    Div16 = SPI_BAUDRATEPRESCALER_16,
+    Div32 = SPI_BAUDRATEPRESCALER_32,
+    Div64 = SPI_BAUDRATEPRESCALER_64,
+    Div128 = SPI_BAUDRATEPRESCALER_128,
+    Div256 = SPI_BAUDRATEPRESCALER_256,
+}
+
+impl SpiPrescaler
+{
+    const ALL: [SpiPrescaler; 8] = [
+        Self::Div2,
+        Self::Div4,
+        Self::Div8,
+        Self::Div16,
+        Self::Div32,
+        Self::Div64,
+        Self::Div128,
+        Self::Div256,
+    ];
+
+    pub const fn divider(&self) -> u32
+    {
+        2 << (*self as u32 >> 3)
+    }
+
+    /// The smallest divider which keeps the SPI clock at or below `frequency`, the largest one if none does.
+    pub fn from_frequency(clock: u32, frequency: u32) -> Self
+    {
+        Self::ALL
+            .into_iter()
+            .find(|prescaler| clock / prescaler.divider() <= frequency)
+            .unwrap_or(Self::Div256)
+    }
+}
+
+/// The format of the transfers, applied by `Spi::configure`, the other settings of the HAL are kept.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct SpiConfig
+{
+    pub mode: SpiMode,
+    pub bit_order: SpiBitOrder,
+    pub frame_size: SpiFrameSize,
+    /// The highest SPI clock in Hz, the real one is the APB clock divided by a power of 2.
+    pub frequency: u32,
+    /// The polynomial of the hardware CRC, none to disable it.
+    pub crc: Option<u16>,
+}
+
+impl SpiConfig
+{
+    /// A config of 8 bits frames, most significant bit first.
+    pub const fn new(mode: SpiMode, frequency: u32) -> Self
+    {
+        SpiConfig {
+            mode,
+            bit_order: SpiBitOrder::MsbFirst,
+            frame_size: SpiFrameSize::Bits8,
+            frequency,
+            crc: None,
+        }
+    }
+
+    pub const fn with_bit_order(mut self, bit_order: SpiBitOrder) -> Self
+    {
+        self.bit_order = bit_order;
+        self
+    }
+
+    pub const fn with_frame_size(mut self, frame_size: SpiFrameSize) -> Self
+    {
+        self.frame_size = frame_size;
+        self
+    }
+
+    /// Enable the hardware CRC, of the size of the frames, with an odd `polynomial`.
+    ///
+    /// The CRC is sent after the data of each transfer, and the CRC received after the data is checked:
+    /// a mismatch fails the transfer and `Spi::error` tells it is a CRC error, or it is sent to the handle
+    /// of `Spi::with_crc_error_event`, else as `SpiEvent::Error`.
+    pub const fn with_crc(mut self, polynomial: u16) -> Self
+    {
+        self.crc = Some(polynomial);
+        self
+    }
+
+    /// The CRC settings of the HAL, the HAL refuses an even polynomial.
+    fn crc(&self) -> Result<(u32, u32)>
+    {
+        match self.crc {
+            None => Ok((SPI_CRCCALCULATION_DISABLE, 7)),
+            Some(polynomial) if polynomial & 1 == 1 => Ok((SPI_CRCCALCULATION_ENABLE, polynomial as u32)),
+            Some(_) => Err(Error::Param),
+        }
+    }
+
+    /// The control register bits of the config, with the CRC polynomial in the upper half, which tell
+    /// apart two configs giving the same transfers.
+    fn format(&self, clock: u32) -> u32
+    {
+        let crc = match self.crc {
+            Some(polynomial) => SPI_CRCCALCULATION_ENABLE | (polynomial as u32) << 16,
+            None => SPI_CRCCALCULATION_DISABLE,
+        };
+
+        self.mode.polarity()
+            | self.mode.phase()
+            | SpiPrescaler::from_frequency(clock, self.frequency) as u32
+            | Into::<u32>::into(self.bit_order)
+            | Into::<u32>::into(self.frame_size)
+            | crc
+    }
+}
+
+/// The error bits reported by the HAL when an error interrupt occurs.
+#[derive(Clone, Copy)]
+pub struct SpiError(u32);
+
+impl SpiError
+{
+    pub fn mode_fault(&self) -> bool
+    {
+        self.0 & HAL_SPI_ERROR_MODF != 0
+    }
+
+    pub fn crc(&self) -> bool
+    {
+        self.0 & HAL_SPI_ERROR_CRC != 0
+    }
+
+    pub fn overrun(&self) -> bool
+    {
+        self.0 & HAL_SPI_ERROR_OVR != 0
+    }
+
+    pub fn frame(&self) -> bool
+    {
+        self.0 & HAL_SPI_ERROR_FRE != 0
+    }
+
+    pub fn dma(&self) -> bool
+    {
+        self.0 & HAL_SPI_ERROR_DMA != 0
+    }
+}
+
+impl From<u32> for SpiError
+{
+    fn from(value: u32) -> Self
+    {
+        SpiError(value)
+    }
+}
+
+impl Into<u32> for SpiError
+{
+    fn into(self) -> u32
+    {
+        self.0
+    }
+}
+
+/// Told instead of `SpiEvent::Error` when the CRC received by a transfer with interrupts or DMA does not match.
+pub type SpiCrcErrorHandle = fn(SpiIdentifies);
+
 pub struct Spi
 {
     spi: SpiIdentifies,
@@ -96,6 +361,192 @@ impl Spi
     {
         Spi { spi }
     }
+
+    /// Initialize the SPI again with another format, the prescaler is chosen from the APB clock of the SPI.
+    pub fn configure(&self, config: &SpiConfig) -> Result<()>
+    {
+        let (crc_calculation, crc_polynomial) = config.crc()?;
+
+        unsafe {
+            let hspi: *mut Hspi = self.spi.into();
+
+            (*hspi).init.clk_polarity = config.mode.polarity();
+            (*hspi).init.clk_phase = config.mode.phase();
+            (*hspi).init.baud_rate_prescaler = SpiPrescaler::from_frequency(self.clock(), config.frequency) as u32;
+            (*hspi).init.first_bit = config.bit_order.into();
+            (*hspi).init.data_size = config.frame_size.into();
+            (*hspi).init.crc_calculation = crc_calculation;
+            (*hspi).init.crc_polynomial = crc_polynomial;
+
+            HAL_SPI_Init(&*hspi).into()
+        }
+    }
+
+    /// The error of the last transfer, to be read from the handle of `SpiEvent::Error` or after a failed
+    /// blocking transfer.
+    pub fn error(&self) -> SpiError
+    {
+        SpiError::from(unsafe { HAL_SPI_GetError(self.spi.into()) })
+    }
+
+    /// Send the CRC errors of the transfers with interrupts or DMA to `handle`, rather than `SpiEvent::Error`.
+    pub fn with_crc_error_event(&self, handle: SpiCrcErrorHandle)
+    {
+        event::EventCenter::crc(self.spi, Some(handle));
+    }
+
+    /// The APB clock in Hz which drives the SPI.
+    pub fn clock(&self) -> u32
+    {
+        let hspi: &Hspi = self.spi.into();
+
+        unsafe {
+            if hspi.instance < crate::memory::APB2PERIPH_BASE {
+                HAL_RCC_GetPCLK1Freq()
+            }
+            else {
+                HAL_RCC_GetPCLK2Freq()
+            }
+        }
+    }
+
+    /// The SPI clock in Hz given by the current prescaler.
+    pub fn frequency(&self) -> u32
+    {
+        let hspi: &Hspi = self.spi.into();
+        let prescaler = (hspi.init.baud_rate_prescaler & SPI_BAUDRATEPRESCALER_256) >> 3;
+
+        self.clock() / (2 << prescaler)
+    }
+
+    pub fn send_words(&self, data: &[u16], timeout: u32) -> Result<()>
+    {
+        let count = words(self.spi, data.len())?;
+
+        self.run(SpiDirection::Transmit, Chunks::new(data.as_ptr() as *const u8, null(), count, 2), timeout)
+    }
+
+    pub fn receive_words(&self, data: &mut [u16], timeout: u32) -> Result<()>
+    {
+        let count = words(self.spi, data.len())?;
+
+        self.run(SpiDirection::Receive, Chunks::new(null(), data.as_ptr() as *const u8, count, 2), timeout)
+    }
+
+    pub fn send_receive_words(&self, tx_data: &[u16], rx_data: &mut [u16], timeout: u32) -> Result<()>
+    {
+        let count = words_pair(self.spi, tx_data.len(), rx_data.len())?;
+        let chunks = Chunks::new(tx_data.as_ptr() as *const u8, rx_data.as_ptr() as *const u8, count, 2);
+
+        self.run(SpiDirection::TransmitReceive, chunks, timeout)
+    }
+
+    pub fn send_words_with_interrupt(&self, data: &[u16]) -> Result<()>
+    {
+        let count = words(self.spi, data.len())?;
+
+        self.start(SpiDirection::Transmit, false, Chunks::new(data.as_ptr() as *const u8, null(), count, 2))
+    }
+
+    pub fn receive_words_with_interrupt(&self, data: &mut [u16]) -> Result<()>
+    {
+        let count = words(self.spi, data.len())?;
+
+        self.start(SpiDirection::Receive, false, Chunks::new(null(), data.as_ptr() as *const u8, count, 2))
+    }
+
+    pub fn send_receive_words_with_interrupt(&self, tx_data: &[u16], rx_data: &mut [u16]) -> Result<()>
+    {
+        let count = words_pair(self.spi, tx_data.len(), rx_data.len())?;
+        let chunks = Chunks::new(tx_data.as_ptr() as *const u8, rx_data.as_ptr() as *const u8, count, 2);
+
+        self.start(SpiDirection::TransmitReceive, false, chunks)
+    }
+
+    pub fn send_words_with_dma(&self, data: &[u16]) -> Result<()>
+    {
+        let count = words(self.spi, data.len())?;
+
+        self.start(SpiDirection::Transmit, true, Chunks::new(data.as_ptr() as *const u8, null(), count, 2))
+    }
+
+    pub fn receive_words_with_dma(&self, data: &mut [u16]) -> Result<()>
+    {
+        let count = words(self.spi, data.len())?;
+
+        self.start(SpiDirection::Receive, true, Chunks::new(null(), data.as_ptr() as *const u8, count, 2))
+    }
+
+    pub fn send_receive_words_with_dma(&self, tx_data: &[u16], rx_data: &mut [u16]) -> Result<()>
+    {
+        let count = words_pair(self.spi, tx_data.len(), rx_data.len())?;
+        let chunks = Chunks::new(tx_data.as_ptr() as *const u8, rx_data.as_ptr() as *const u8, count, 2);
+
+        self.start(SpiDirection::TransmitReceive, true, chunks)
+    }
+
+    fn run(&self, direction: SpiDirection, chunks: Chunks, timeout: u32) -> Result<()>
+    {
+        spi_transfer::run(self.spi, direction, chunks, timeout)
+    }
+
+    /// Start an interrupt or DMA transfer, only its last chunk sends the end event.
+    fn start(&self, direction: SpiDirection, dma: bool, chunks: Chunks) -> Result<()>
+    {
+        SpiTransfer::new(direction, dma, chunks).start(self.spi)
+    }
+}
+
+/// The HAL counts frames, not bytes: a byte buffer needs 8 bits frames, and its length is the frame count.
+fn frames(spi: SpiIdentifies, size: usize) -> Result<usize>
+{
+    let hspi: &Hspi = spi.into();
+
+    if hspi.init.data_size != SPI_DATASIZE_8BIT {
+        return Err(Error::Param);
+    }
+
+    checked(hspi, size)
+}
+
+fn frames_pair(spi: SpiIdentifies, tx_size: usize, rx_size: usize) -> Result<usize>
+{
+    if tx_size != rx_size {
+        return Err(Error::Param);
+    }
+
+    frames(spi, tx_size)
+}
+
+/// A word buffer needs 16 bits frames, one word per frame.
+fn words(spi: SpiIdentifies, size: usize) -> Result<usize>
+{
+    let hspi: &Hspi = spi.into();
+
+    if hspi.init.data_size != SPI_DATASIZE_16BIT {
+        return Err(Error::Param);
+    }
+
+    checked(hspi, size)
+}
+
+fn words_pair(spi: SpiIdentifies, tx_size: usize, rx_size: usize) -> Result<usize>
+{
+    if tx_size != rx_size {
+        return Err(Error::Param);
+    }
+
+    words(spi, tx_size)
+}
+
+/// Each call of the HAL has its own CRC, a transfer split into chunks would carry several of them.
+fn checked(hspi: &Hspi, size: usize) -> Result<usize>
+{
+    if hspi.init.crc_calculation == SPI_CRCCALCULATION_ENABLE && size > CHUNK_SIZE {
+        return Err(Error::Param);
+    }
+
+    Ok(size)
 }
 
 impl SpiDevice for Spi
@@ -107,47 +558,65 @@ impl SpiDevice for Spi
 
     fn send(&self, data: &[u8], timeout: u32) -> Result<()>
     {
-        unsafe { HAL_SPI_Transmit(self.spi.into(), data.as_ptr(), data.len() as u16, timeout).into() }
+        let count = frames(self.spi, data.len())?;
+
+        self.run(SpiDirection::Transmit, Chunks::new(data.as_ptr(), null(), count, 1), timeout)
     }
 
     fn receive(&self, data: &mut [u8], timeout: u32) -> Result<()>
     {
-        unsafe { HAL_SPI_Receive(self.spi.into(), data.as_ptr(), data.len() as u16, timeout).into() }
+        let count = frames(self.spi, data.len())?;
+
+        self.run(SpiDirection::Receive, Chunks::new(null(), data.as_ptr(), count, 1), timeout)
     }
 
     fn send_receive(&self, tx_data: &[u8], rx_data: &mut [u8], timeout: u32) -> Result<()>
     {
-        unsafe { HAL_SPI_TransmitReceive(self.spi.into(), tx_data.as_ptr(), rx_data.as_ptr(), tx_data.len() as u16, timeout).into() }
+        let count = frames_pair(self.spi, tx_data.len(), rx_data.len())?;
+
+        self.run(SpiDirection::TransmitReceive, Chunks::new(tx_data.as_ptr(), rx_data.as_ptr(), count, 1), timeout)
     }
 
     fn send_with_interrupt(&self, data: &[u8]) -> Result<()>
     {
-        unsafe { HAL_SPI_Transmit_IT(self.spi.into(), data.as_ptr(), data.len() as u16).into() }
+        let count = frames(self.spi, data.len())?;
+
+        self.start(SpiDirection::Transmit, false, Chunks::new(data.as_ptr(), null(), count, 1))
     }
 
     fn receive_with_interrupt(&self, data: &mut [u8]) -> Result<()>
     {
-        unsafe { HAL_SPI_Receive_IT(self.spi.into(), data.as_ptr(), data.len() as u16).into() }
+        let count = frames(self.spi, data.len())?;
+
+        self.start(SpiDirection::Receive, false, Chunks::new(null(), data.as_ptr(), count, 1))
     }
 
     fn send_receive_with_interrupt(&self, tx_data: &[u8], rx_data: &mut [u8]) -> Result<()>
     {
-        unsafe { HAL_SPI_TransmitReceive_IT(self.spi.into(), tx_data.as_ptr(), rx_data.as_ptr(), tx_data.len() as u16).into() }
+        let count = frames_pair(self.spi, tx_data.len(), rx_data.len())?;
+
+        self.start(SpiDirection::TransmitReceive, false, Chunks::new(tx_data.as_ptr(), rx_data.as_ptr(), count, 1))
     }
 
     fn send_with_dma(&self, data: &[u8]) -> Result<()>
     {
-        unsafe { HAL_SPI_Transmit_DMA(self.spi.into(), data.as_ptr(), data.len() as u16).into() }
+        let count = frames(self.spi, data.len())?;
+
+        self.start(SpiDirection::Transmit, true, Chunks::new(data.as_ptr(), null(), count, 1))
     }
 
     fn receive_with_dma(&self, data: &mut [u8]) -> Result<()>
     {
-        unsafe { HAL_SPI_Receive_DMA(self.spi.into(), data.as_ptr(), data.len() as u16).into() }
+        let count = frames(self.spi, data.len())?;
+
+        self.start(SpiDirection::Receive, true, Chunks::new(null(), data.as_ptr(), count, 1))
     }
 
     fn send_receive_with_dma(&self, tx_data: &[u8], rx_data: &mut [u8]) -> Result<()>
     {
-        unsafe { HAL_SPI_TransmitReceive_DMA(self.spi.into(), tx_data.as_ptr(), rx_data.as_ptr(), tx_data.len() as u16).into() }
+        let count = frames_pair(self.spi, tx_data.len(), rx_data.len())?;
+
+        self.start(SpiDirection::TransmitReceive, true, Chunks::new(tx_data.as_ptr(), rx_data.as_ptr(), count, 1))
     }
 
     fn abort(&self) -> Result<()>
@@ -158,18 +627,59 @@ impl SpiDevice for Spi
 
 mod event
 {
+    use core::sync::atomic::AtomicBool;
+    use core::sync::atomic::Ordering;
+
+    use escw_mcu::common::Result;
+    use escw_mcu::peripheral::io::IoDevice;
+    use escw_mcu::peripheral::io::IoState;
     use escw_mcu::peripheral::spi::SpiEvent;
     use escw_mcu::peripheral::spi::SpiEventHandle;
 
+    use crate::hal::io::GPIO_PIN_SET;
     use crate::hal::spi::*;
-
+    use crate::peripheral::delay::delay_ns;
+    use crate::peripheral::io::Io;
+
+    use super::spi_slave::SpiSlaveContext;
+    use super::spi_transaction::SpiChain;
+    use super::spi_transfer::SpiTransfer;
+    use super::SpiCrcErrorHandle;
+    use super::SpiError;
     use super::SpiIdentifies;
 
     static mut EVENT_CENTER: EventCenter = EventCenter::new();
 
+    static LOCKS: [AtomicBool; SpiIdentifies::count()] = [const { AtomicBool::new(false) }; SpiIdentifies::count()];
+
+    /// An asynchronous transfer under one chip select, which owns the bus until it ends.
+    ///
+    /// A transfer with a chain runs all the steps of the chain and ends with one `SpiEvent::TxRxCompleted`.
+    #[derive(Clone, Copy)]
+    pub struct Transaction
+    {
+        pub cs: Io,
+        pub hold_ns: u32,
+        pub handle: Option<SpiEventHandle>,
+        pub chain: Option<SpiChain>,
+    }
+
+    impl Transaction
+    {
+        pub fn deselect(&self)
+        {
+            delay_ns(self.hold_ns);
+            self.cs.set_state(IoState::from(GPIO_PIN_SET));
+        }
+    }
+
     pub struct EventCenter
     {
         handle: [Option<SpiEventHandle>; SpiIdentifies::count()],
+        transaction: [Option<Transaction>; SpiIdentifies::count()],
+        transfer: [Option<SpiTransfer>; SpiIdentifies::count()],
+        slave: [Option<SpiSlaveContext>; SpiIdentifies::count()],
+        crc: [Option<SpiCrcErrorHandle>; SpiIdentifies::count()],
     }
 
     impl EventCenter
@@ -178,6 +688,10 @@ mod event
         {
             EventCenter {
                 handle: [None; SpiIdentifies::count()],
+                transaction: [None; SpiIdentifies::count()],
+                transfer: [None; SpiIdentifies::count()],
+                slave: [None; SpiIdentifies::count()],
+                crc: [None; SpiIdentifies::count()],
             }
         }
 
@@ -188,14 +702,190 @@ mod event
             }
         }
 
-        pub fn invoke(spi: SpiIdentifies, event: SpiEvent)
+        pub fn get(spi: SpiIdentifies) -> Option<SpiEventHandle>
+        {
+            unsafe { EVENT_CENTER.handle[spi as usize] }
+        }
+
+        /// Take the bus for one device, false if another device has it.
+        pub fn lock(spi: SpiIdentifies) -> bool
+        {
+            LOCKS[spi as usize].compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed).is_ok()
+        }
+
+        pub fn unlock(spi: SpiIdentifies)
+        {
+            LOCKS[spi as usize].store(false, Ordering::Release);
+        }
+
+        /// Route the events to the transaction until it ends, then release its chip select and the bus.
+        pub fn begin(spi: SpiIdentifies, transaction: Transaction)
+        {
+            unsafe {
+                EVENT_CENTER.transaction[spi as usize] = Some(transaction);
+            }
+        }
+
+        /// Whether the running transaction is the one of the device selected by `cs`.
+        pub fn selects(spi: SpiIdentifies, cs: &Io) -> bool
+        {
+            let transaction = unsafe { &*core::ptr::addr_of!(EVENT_CENTER.transaction[spi as usize]) };
+
+            transaction.as_ref().is_some_and(|transaction| {
+                Into::<u32>::into(transaction.cs.port()) == Into::<u32>::into(cs.port())
+                    && Into::<u16>::into(transaction.cs.pin()) == Into::<u16>::into(cs.pin())
+            })
+        }
+
+        /// Forget the transaction, for a transfer which failed to start.
+        pub fn cancel(spi: SpiIdentifies)
+        {
+            unsafe {
+                EVENT_CENTER.transaction[spi as usize] = None;
+            }
+        }
+
+        /// Keep a transfer too long for one call of the HAL, its end events start the next chunks.
+        pub fn follow(spi: SpiIdentifies, transfer: Option<SpiTransfer>)
         {
             unsafe {
-                if let Some(invoke) = EVENT_CENTER.handle[spi as usize].as_ref() {
-                    invoke(event);
+                EVENT_CENTER.transfer[spi as usize] = transfer;
+            }
+        }
+
+        /// Start the next transfer of the chain of the transaction, false when the chain is done.
+        pub fn advance(spi: SpiIdentifies) -> Result<bool>
+        {
+            unsafe {
+                let transaction = &mut *core::ptr::addr_of_mut!(EVENT_CENTER.transaction[spi as usize]);
+
+                match transaction.as_mut().and_then(|transaction| transaction.chain.as_mut()) {
+                    Some(chain) => chain.advance(spi),
+                    None => Ok(false),
                 }
             }
         }
+
+        /// Keep the buffers of a running slave, whose frames are ended by its NSS instead of the HAL.
+        pub fn set_slave(spi: SpiIdentifies, slave: Option<SpiSlaveContext>)
+        {
+            unsafe {
+                EVENT_CENTER.slave[spi as usize] = slave;
+            }
+        }
+
+        pub fn slave(spi: SpiIdentifies) -> Option<&'static mut SpiSlaveContext>
+        {
+            unsafe { (*core::ptr::addr_of_mut!(EVENT_CENTER.slave[spi as usize])).as_mut() }
+        }
+
+        pub fn crc(spi: SpiIdentifies, handle: Option<SpiCrcErrorHandle>)
+        {
+            unsafe {
+                EVENT_CENTER.crc[spi as usize] = handle;
+            }
+        }
+
+        /// Send a CRC error to its own handle, true when it has been sent there.
+        fn crc_failed(spi: SpiIdentifies, event: &SpiEvent) -> bool
+        {
+            let handle = unsafe { EVENT_CENTER.crc[spi as usize] };
+
+            match (event, handle) {
+                (SpiEvent::Error, Some(handle)) if SpiError::from(unsafe { HAL_SPI_GetError(spi.into()) }).crc() => {
+                    handle(spi);
+                    true
+                }
+                _ => false,
+            }
+        }
+
+        /// Send an event straight to the handle of the instance.
+        pub fn forward(spi: SpiIdentifies, event: SpiEvent)
+        {
+            if Self::crc_failed(spi, &event) {
+                return;
+            }
+
+            if let Some(invoke) = Self::get(spi) {
+                invoke(event);
+            }
+        }
+
+        pub fn invoke(spi: SpiIdentifies, mut event: SpiEvent)
+        {
+            // The DMA of a slave ends with its frame, only its errors are told here.
+            if let Some(slave) = Self::slave(spi) {
+                if let SpiEvent::Error = event {
+                    let error: SpiError = unsafe { HAL_SPI_GetError(spi.into()) }.into();
+
+                    if error.overrun() {
+                        slave.errors.overrun += 1;
+                    }
+
+                    Self::forward(spi, event);
+                }
+
+                return;
+            }
+
+            unsafe {
+                let half = matches!(event, SpiEvent::TxHalf | SpiEvent::RxHalf | SpiEvent::TxRxHalf);
+                let transfer = &mut *core::ptr::addr_of_mut!(EVENT_CENTER.transfer[spi as usize]);
+
+                // The half events of the chunks mean nothing for the whole transfer.
+                if let Some(current) = transfer.as_mut() {
+                    if half {
+                        return;
+                    }
+
+                    if !matches!(event, SpiEvent::Error | SpiEvent::TxRxAborted) {
+                        match current.advance(spi) {
+                            Ok(true) => return,
+                            Ok(false) => {}
+                            Err(_) => event = SpiEvent::Error,
+                        }
+                    }
+
+                    *transfer = None;
+                }
+
+                if let Some(transaction) = EVENT_CENTER.transaction[spi as usize] {
+
+                    if transaction.chain.is_some() {
+                        if half {
+                            return;
+                        }
+
+                        if !matches!(event, SpiEvent::Error | SpiEvent::TxRxAborted) {
+                            match Self::advance(spi) {
+                                Ok(true) => return,
+                                Ok(false) => event = SpiEvent::TxRxCompleted,
+                                Err(_) => event = SpiEvent::Error,
+                            }
+                        }
+                    }
+
+                    if !half {
+                        EVENT_CENTER.transaction[spi as usize] = None;
+                        transaction.deselect();
+                        Self::unlock(spi);
+                    }
+
+                    if Self::crc_failed(spi, &event) {
+                        return;
+                    }
+
+                    if let Some(invoke) = transaction.handle {
+                        invoke(event);
+                    }
+
+                    return;
+                }
+
+                Self::forward(spi, event);
+            }
+        }
     }
 
     #[no_mangle]
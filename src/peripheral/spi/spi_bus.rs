@@ -0,0 +1,302 @@
+use core::sync::atomic::AtomicU32;
+use core::sync::atomic::Ordering;
+
+use escw_mcu::common::Error;
+use escw_mcu::common::Result;
+use escw_mcu::peripheral::io::IoDevice;
+use escw_mcu::peripheral::io::IoState;
+use escw_mcu::peripheral::spi::SpiDevice;
+use escw_mcu::peripheral::spi::SpiEventHandle;
+
+use crate::hal::io::GPIO_PIN_RESET;
+use crate::hal::io::GPIO_PIN_SET;
+use crate::hal::system::HAL_GetTick;
+use crate::peripheral::io::Io;
+
+use super::event::EventCenter;
+use super::event::Transaction;
+use super::spi_transaction::SpiChain;
+use super::*;
+
+const FORMAT_UNKNOWN: u32 = u32::MAX;
+
+/// One SPI shared by several devices, each one with its own chip select and format.
+///
+/// The transfers of the devices never overlap, a device waits or is refused while another one,
+/// including its interrupt or DMA transfer, owns the bus.
+pub struct SpiBus
+{
+    spi: Spi,
+    format: AtomicU32,
+}
+
+impl SpiBus
+{
+    pub fn new(spi: Spi) -> Self
+    {
+        SpiBus {
+            spi,
+            format: AtomicU32::new(FORMAT_UNKNOWN),
+        }
+    }
+
+    /// Attach a device to the bus, its chip select is released at once.
+    pub fn device(&self, cs: Io, config: SpiConfig) -> SpiDeviceHandle<'_>
+    {
+        cs.set_state(IoState::from(GPIO_PIN_SET));
+
+        SpiDeviceHandle {
+            bus: self,
+            cs,
+            config,
+            setup_ns: 0,
+            hold_ns: 0,
+            handle: None,
+        }
+    }
+
+    fn acquire(&self, timeout: u32) -> Result<()>
+    {
+        let start = unsafe { HAL_GetTick() };
+
+        while !EventCenter::lock(self.spi.spi) {
+            if unsafe { HAL_GetTick() }.wrapping_sub(start) >= timeout {
+                return Err(Error::PeripheralBusy);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Initialize the SPI again only when the device uses another format than the last one.
+    fn apply(&self, config: &SpiConfig) -> Result<()>
+    {
+        let format = config.format(self.spi.clock());
+
+        if self.format.load(Ordering::Relaxed) != format {
+            self.format.store(FORMAT_UNKNOWN, Ordering::Relaxed);
+            self.spi.configure(config)?;
+            self.format.store(format, Ordering::Relaxed);
+        }
+
+        Ok(())
+    }
+}
+
+pub struct SpiDeviceHandle<'a>
+{
+    bus: &'a SpiBus,
+    cs: Io,
+    config: SpiConfig,
+    setup_ns: u32,
+    hold_ns: u32,
+    handle: Option<SpiEventHandle>,
+}
+
+impl SpiDeviceHandle<'_>
+{
+    /// Wait `setup_ns` from the chip select to the first clock, and `hold_ns` from the last clock to the release.
+    pub fn with_timing(mut self, setup_ns: u32, hold_ns: u32) -> Self
+    {
+        self.setup_ns = setup_ns;
+        self.hold_ns = hold_ns;
+        self
+    }
+
+    /// Run the steps under the chip select of the device, waiting the bus for `timeout` at most.
+    pub fn transaction(&self, operations: &mut [SpiOperation], timeout: u32) -> Result<()>
+    {
+        self.blocking(timeout, |spi| spi_transaction::run(spi, operations, timeout))
+    }
+
+    /// Run the steps with DMA under the chip select of the device, the handle given to `with_event`
+    /// gets one `SpiEvent::TxRxCompleted` once all of them are done, or the event of the failed one.
+    pub fn transaction_with_dma(&self, operations: &'static mut [SpiOperation<'static>]) -> Result<()>
+    {
+        spi_transaction::check_dma(operations)?;
+
+        let spi = self.bus.spi.spi;
+
+        self.start_with(Some(SpiChain::new(operations)), |_| EventCenter::advance(spi).map(|_| ()))
+    }
+
+    pub fn send_words(&self, data: &[u16], timeout: u32) -> Result<()>
+    {
+        self.blocking(timeout, |spi| spi.send_words(data, timeout))
+    }
+
+    pub fn receive_words(&self, data: &mut [u16], timeout: u32) -> Result<()>
+    {
+        self.blocking(timeout, |spi| spi.receive_words(data, timeout))
+    }
+
+    pub fn send_receive_words(&self, tx_data: &[u16], rx_data: &mut [u16], timeout: u32) -> Result<()>
+    {
+        self.blocking(timeout, |spi| spi.send_receive_words(tx_data, rx_data, timeout))
+    }
+
+    pub fn send_words_with_interrupt(&self, data: &[u16]) -> Result<()>
+    {
+        self.start(|spi| spi.send_words_with_interrupt(data))
+    }
+
+    pub fn receive_words_with_interrupt(&self, data: &mut [u16]) -> Result<()>
+    {
+        self.start(|spi| spi.receive_words_with_interrupt(data))
+    }
+
+    pub fn send_receive_words_with_interrupt(&self, tx_data: &[u16], rx_data: &mut [u16]) -> Result<()>
+    {
+        self.start(|spi| spi.send_receive_words_with_interrupt(tx_data, rx_data))
+    }
+
+    pub fn send_words_with_dma(&self, data: &[u16]) -> Result<()>
+    {
+        self.start(|spi| spi.send_words_with_dma(data))
+    }
+
+    pub fn receive_words_with_dma(&self, data: &mut [u16]) -> Result<()>
+    {
+        self.start(|spi| spi.receive_words_with_dma(data))
+    }
+
+    pub fn send_receive_words_with_dma(&self, tx_data: &[u16], rx_data: &mut [u16]) -> Result<()>
+    {
+        self.start(|spi| spi.send_receive_words_with_dma(tx_data, rx_data))
+    }
+
+    fn transaction_of(&self, chain: Option<SpiChain>) -> Transaction
+    {
+        Transaction {
+            cs: self.cs,
+            hold_ns: self.hold_ns,
+            handle: self.handle,
+            chain,
+        }
+    }
+
+    fn select(&self)
+    {
+        self.cs.set_state(IoState::from(GPIO_PIN_RESET));
+        delay_ns(self.setup_ns);
+    }
+
+    /// Run a blocking transfer with the bus and the chip select, waiting the bus for `timeout` at most.
+    fn blocking<F>(&self, timeout: u32, transfer: F) -> Result<()>
+    where
+        F: FnOnce(&Spi) -> Result<()>,
+    {
+        self.bus.acquire(timeout)?;
+
+        let result = self.bus.apply(&self.config).and_then(|_| {
+            self.select();
+            let result = transfer(&self.bus.spi);
+            self.transaction_of(None).deselect();
+            result
+        });
+
+        EventCenter::unlock(self.bus.spi.spi);
+
+        result
+    }
+
+    /// Start an asynchronous transfer, the bus and the chip select are released by its end event.
+    fn start<F>(&self, transfer: F) -> Result<()>
+    where
+        F: FnOnce(&Spi) -> Result<()>,
+    {
+        self.start_with(None, transfer)
+    }
+
+    fn start_with<F>(&self, chain: Option<SpiChain>, transfer: F) -> Result<()>
+    where
+        F: FnOnce(&Spi) -> Result<()>,
+    {
+        let spi = self.bus.spi.spi;
+
+        if !EventCenter::lock(spi) {
+            return Err(Error::PeripheralBusy);
+        }
+
+        if let Err(error) = self.bus.apply(&self.config) {
+            EventCenter::unlock(spi);
+            return Err(error);
+        }
+
+        self.select();
+        EventCenter::begin(spi, self.transaction_of(chain));
+
+        if let Err(error) = transfer(&self.bus.spi) {
+            EventCenter::cancel(spi);
+            self.transaction_of(None).deselect();
+            EventCenter::unlock(spi);
+            return Err(error);
+        }
+
+        Ok(())
+    }
+}
+
+impl SpiDevice for SpiDeviceHandle<'_>
+{
+    fn with_event(&mut self, handle: SpiEventHandle)
+    {
+        self.handle = Some(handle);
+    }
+
+    fn send(&self, data: &[u8], timeout: u32) -> Result<()>
+    {
+        self.blocking(timeout, |spi| spi.send(data, timeout))
+    }
+
+    fn receive(&self, data: &mut [u8], timeout: u32) -> Result<()>
+    {
+        self.blocking(timeout, |spi| spi.receive(data, timeout))
+    }
+
+    fn send_receive(&self, tx_data: &[u8], rx_data: &mut [u8], timeout: u32) -> Result<()>
+    {
+        self.blocking(timeout, |spi| spi.send_receive(tx_data, rx_data, timeout))
+    }
+
+    fn send_with_interrupt(&self, data: &[u8]) -> Result<()>
+    {
+        self.start(|spi| spi.send_with_interrupt(data))
+    }
+
+    fn receive_with_interrupt(&self, data: &mut [u8]) -> Result<()>
+    {
+        self.start(|spi| spi.receive_with_interrupt(data))
+    }
+
+    fn send_receive_with_interrupt(&self, tx_data: &[u8], rx_data: &mut [u8]) -> Result<()>
+    {
+        self.start(|spi| spi.send_receive_with_interrupt(tx_data, rx_data))
+    }
+
+    fn send_with_dma(&self, data: &[u8]) -> Result<()>
+    {
+        self.start(|spi| spi.send_with_dma(data))
+    }
+
+    fn receive_with_dma(&self, data: &mut [u8]) -> Result<()>
+    {
+        self.start(|spi| spi.receive_with_dma(data))
+    }
+
+    fn send_receive_with_dma(&self, tx_data: &[u8], rx_data: &mut [u8]) -> Result<()>
+    {
+        self.start(|spi| spi.send_receive_with_dma(tx_data, rx_data))
+    }
+
+    /// Abort the transfer of this device going on the bus, its end event releases the bus. The transfer
+    /// of another device is refused.
+    fn abort(&self) -> Result<()>
+    {
+        if !EventCenter::selects(self.bus.spi.spi, &self.cs) {
+            return Err(Error::PeripheralBusy);
+        }
+
+        self.bus.spi.abort()
+    }
+}
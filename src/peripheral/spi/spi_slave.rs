@@ -0,0 +1,218 @@
+use escw_mcu::common::Error;
+use escw_mcu::common::Result;
+use escw_mcu::peripheral::io::IoDevice;
+use escw_mcu::peripheral::spi::SpiEvent;
+use escw_mcu::peripheral::spi::SpiEventHandle;
+
+use crate::hal::dma::DmaHandle;
+use crate::hal::io::GPIO_PIN_SET;
+use crate::peripheral::chunk::CHUNK_SIZE;
+use crate::peripheral::io::Io;
+use crate::peripheral::io::IoPin;
+
+use super::event::EventCenter;
+use super::*;
+
+/// The number of each kind of error seen on a slave.
+#[derive(Clone, Copy, Default)]
+pub struct SpiSlaveErrorCount
+{
+    /// The master clocked more bytes than the response buffer holds.
+    pub underrun: u32,
+    /// A received byte was lost before the DMA took it.
+    pub overrun: u32,
+}
+
+/// The buffers and the frames of a slave, kept by the event center while it runs.
+#[derive(Clone, Copy)]
+pub struct SpiSlaveContext
+{
+    tx: *mut u8,
+    rx: *mut u8,
+    size: usize,
+    /// The half of the receive buffer the running frame goes to.
+    active: usize,
+    /// The half and the length of the last frame received.
+    frame: Option<(usize, usize)>,
+    pub errors: SpiSlaveErrorCount,
+}
+
+/// An SPI slave selected by its NSS pin, which answers every frame with the same preloaded response.
+///
+/// The end of a frame is the rise of NSS, seen by the EXTI of the pin: forward the `Io` events to
+/// `on_io_event`. Each frame sends `SpiEvent::RxCompleted` to the handle given to `with_event`,
+/// an overrun or underrun sends `SpiEvent::Error`.
+pub struct SpiSlave
+{
+    spi: SpiIdentifies,
+    nss: Io,
+}
+
+impl SpiSlave
+{
+    pub fn new(spi: SpiIdentifies, nss: Io) -> Self
+    {
+        SpiSlave { spi, nss }
+    }
+
+    pub fn with_event(&mut self, handle: SpiEventHandle)
+    {
+        EventCenter::set(self.spi, handle)
+    }
+
+    /// Initialize the SPI as a full duplex slave with hardware NSS, the frequency of the config is not used.
+    pub fn init(&self, config: &SpiConfig) -> Result<()>
+    {
+        let (crc_calculation, crc_polynomial) = config.crc()?;
+
+        unsafe {
+            let hspi: *mut Hspi = self.spi.into();
+
+            (*hspi).init.mode = SPI_MODE_SLAVE;
+            (*hspi).init.direction = SPI_DIRECTION_2LINES;
+            (*hspi).init.nss = SPI_NSS_HARD_INPUT;
+            (*hspi).init.clk_polarity = config.mode.polarity();
+            (*hspi).init.clk_phase = config.mode.phase();
+            (*hspi).init.first_bit = config.bit_order.into();
+            (*hspi).init.data_size = config.frame_size.into();
+            (*hspi).init.crc_calculation = crc_calculation;
+            (*hspi).init.crc_polynomial = crc_polynomial;
+
+            HAL_SPI_Init(&*hspi).into()
+        }
+    }
+
+    /// Start to answer the frames with `tx` and take them into `rx`.
+    ///
+    /// The receive buffer is split in two halves which take the frames in turn, so a frame stays
+    /// readable while the next one arrives. A frame is at most half of `rx` long, `tx` as long as that.
+    pub fn start(&self, tx: &'static mut [u8], rx: &'static mut [u8]) -> Result<()>
+    {
+        let size = rx.len() / 2;
+
+        if size == 0 || size > CHUNK_SIZE || tx.len() < size {
+            return Err(Error::Param);
+        }
+
+        let context = SpiSlaveContext {
+            tx: tx.as_mut_ptr(),
+            rx: rx.as_mut_ptr(),
+            size,
+            active: 0,
+            frame: None,
+            errors: SpiSlaveErrorCount::default(),
+        };
+
+        unsafe { HAL_SPI_Abort(self.spi.into()) };
+        EventCenter::set_slave(self.spi, Some(context));
+
+        self.arm()
+    }
+
+    pub fn stop(&self) -> Result<()>
+    {
+        EventCenter::set_slave(self.spi, None);
+
+        unsafe { HAL_SPI_Abort(self.spi.into()).into() }
+    }
+
+    /// Replace the response of the next frames, the rest of the response buffer is kept.
+    ///
+    /// Refused while the master selects the slave, as the running frame sends the response.
+    pub fn preload(&self, response: &[u8]) -> Result<()>
+    {
+        let context = EventCenter::slave(self.spi).ok_or(Error::Param)?;
+
+        if response.len() > context.size {
+            return Err(Error::Param);
+        }
+
+        if !self.released() {
+            return Err(Error::PeripheralBusy);
+        }
+
+        // The DMA has already loaded the first byte into the data register, start it again.
+        unsafe {
+            HAL_SPI_Abort(self.spi.into());
+            core::ptr::copy_nonoverlapping(response.as_ptr(), context.tx, response.len());
+        }
+
+        self.arm()
+    }
+
+    /// Forward the `Io` events here from the handle given to `Io::with_event`, the other pins are ignored.
+    pub fn on_io_event(&self, pin: IoPin)
+    {
+        if pin as u16 != self.nss.pin() as u16 || !self.released() {
+            return;
+        }
+
+        let context = match EventCenter::slave(self.spi) {
+            Some(context) => context,
+            None => return,
+        };
+
+        let hspi: &Hspi = self.spi.into();
+
+        let (received, underrun) = unsafe {
+            let registers = hspi.instance as *const SpiRegisters;
+            let status = core::ptr::read_volatile(core::ptr::addr_of!((*registers).sr));
+            let received = context.size - hspi.hdmarx.as_ref().map_or(0, DmaHandle::remaining) as usize;
+
+            HAL_SPI_Abort(hspi);
+
+            // A byte after the end of the buffer is a byte the response did not cover.
+            (received, received == context.size && status & (SPI_SR_RXNE | SPI_SR_OVR) != 0)
+        };
+
+        context.frame = Some((context.active, received));
+        context.active ^= 1;
+
+        if underrun {
+            context.errors.underrun += 1;
+        }
+
+        let restarted = self.arm();
+
+        EventCenter::forward(self.spi, SpiEvent::RxCompleted);
+
+        if underrun || restarted.is_err() {
+            EventCenter::forward(self.spi, SpiEvent::Error);
+        }
+    }
+
+    /// The last frame received, it stays valid until the end of the next frame.
+    pub fn frame(&self) -> Option<&[u8]>
+    {
+        let context = EventCenter::slave(self.spi)?;
+        let (half, size) = context.frame?;
+
+        Some(unsafe { core::slice::from_raw_parts(context.rx.add(half * context.size), size) })
+    }
+
+    pub fn error_count(&self) -> SpiSlaveErrorCount
+    {
+        EventCenter::slave(self.spi).map_or(SpiSlaveErrorCount::default(), |context| context.errors)
+    }
+
+    pub fn clear_error_count(&self)
+    {
+        if let Some(context) = EventCenter::slave(self.spi) {
+            context.errors = SpiSlaveErrorCount::default();
+        }
+    }
+
+    fn released(&self) -> bool
+    {
+        Into::<u32>::into(self.nss.state()) == GPIO_PIN_SET
+    }
+
+    /// Start the DMA of the next frame into the active half of the receive buffer.
+    fn arm(&self) -> Result<()>
+    {
+        let context = EventCenter::slave(self.spi).ok_or(Error::Param)?;
+        let rx = unsafe { context.rx.add(context.active * context.size) };
+
+        unsafe { HAL_SPI_TransmitReceive_DMA(self.spi.into(), context.tx, rx, context.size as u16).into() }
+    }
+}
@@ -0,0 +1,188 @@
+use escw_mcu::common::Error;
+use escw_mcu::common::Result;
+use escw_mcu::peripheral::io::IoDevice;
+use escw_mcu::peripheral::io::IoState;
+
+use crate::hal::io::GPIO_PIN_RESET;
+use crate::peripheral::io::Io;
+
+use super::event::EventCenter;
+use super::event::Transaction;
+use super::*;
+
+/// One step of a transaction, all the steps run back to back under one chip select.
+pub enum SpiOperation<'a>
+{
+    Write(&'a [u8]),
+    Read(&'a mut [u8]),
+    /// Receive into the first buffer while sending the second one, both of the same length.
+    Transfer(&'a mut [u8], &'a [u8]),
+    /// Send the buffer and replace it with the received bytes.
+    TransferInPlace(&'a mut [u8]),
+    /// Keep the chip select and wait, run by busy waiting, also from the interrupt of a DMA transaction.
+    DelayNs(u32),
+}
+
+impl SpiOperation<'_>
+{
+    fn run(&mut self, spi: &Spi, timeout: u32) -> Result<()>
+    {
+        match self {
+            Self::Write(data) => spi.send(data, timeout),
+            Self::Read(data) => spi.receive(data, timeout),
+            Self::Transfer(rx_data, tx_data) => spi.send_receive(tx_data, rx_data, timeout),
+            Self::TransferInPlace(data) => {
+                let count = frames(spi.spi, data.len())?;
+
+                spi.run(SpiDirection::TransmitReceive, Chunks::new(data.as_ptr(), data.as_ptr(), count, 1), timeout)
+            }
+            Self::DelayNs(ns) => {
+                delay_ns(*ns);
+                Ok(())
+            }
+        }
+    }
+
+    /// Start the DMA transfer of the step, false if the step is a delay, which is done at once.
+    fn start(&mut self, spi: SpiIdentifies) -> Result<bool>
+    {
+        let (direction, chunks) = match self {
+            Self::Write(data) => (SpiDirection::Transmit, Chunks::new(data.as_ptr(), null(), frames(spi, data.len())?, 1)),
+            Self::Read(data) => (SpiDirection::Receive, Chunks::new(null(), data.as_ptr(), frames(spi, data.len())?, 1)),
+            Self::Transfer(rx_data, tx_data) => (
+                SpiDirection::TransmitReceive,
+                Chunks::new(tx_data.as_ptr(), rx_data.as_ptr(), frames(spi, tx_data.len())?, 1),
+            ),
+            Self::TransferInPlace(data) => (SpiDirection::TransmitReceive, Chunks::new(data.as_ptr(), data.as_ptr(), frames(spi, data.len())?, 1)),
+            Self::DelayNs(ns) => {
+                delay_ns(*ns);
+                return Ok(false);
+            }
+        };
+
+        SpiTransfer::new(direction, true, chunks).start(spi).map(|_| true)
+    }
+}
+
+/// The steps of a DMA transaction, the next one is started from the end event of the previous one.
+#[derive(Clone, Copy)]
+pub struct SpiChain
+{
+    operations: *mut SpiOperation<'static>,
+    count: usize,
+    next: usize,
+}
+
+impl SpiChain
+{
+    pub fn new(operations: &'static mut [SpiOperation<'static>]) -> Self
+    {
+        SpiChain {
+            operations: operations.as_mut_ptr(),
+            count: operations.len(),
+            next: 0,
+        }
+    }
+
+    /// Start the next transfer, false when no transfer is left.
+    pub fn advance(&mut self, spi: SpiIdentifies) -> Result<bool>
+    {
+        while self.next < self.count {
+            let operation = unsafe { &mut *self.operations.add(self.next) };
+
+            self.next += 1;
+
+            if operation.start(spi)? {
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+}
+
+/// The steps are valid when the two buffers of every transfer have the same length.
+fn check(operations: &[SpiOperation]) -> Result<()>
+{
+    for operation in operations {
+        if let SpiOperation::Transfer(rx_data, tx_data) = operation {
+            if rx_data.len() != tx_data.len() {
+                return Err(Error::Param);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// A DMA transaction needs at least one transfer to end with an event.
+pub(super) fn check_dma(operations: &[SpiOperation]) -> Result<()>
+{
+    if operations.iter().all(|operation| matches!(operation, SpiOperation::DelayNs(_))) {
+        return Err(Error::Param);
+    }
+
+    check(operations)
+}
+
+/// Run the steps one after the other, stopping at the first failure.
+pub(super) fn run(spi: &Spi, operations: &mut [SpiOperation], timeout: u32) -> Result<()>
+{
+    check(operations)?;
+
+    for operation in operations.iter_mut() {
+        operation.run(spi, timeout)?;
+    }
+
+    Ok(())
+}
+
+impl Spi
+{
+    /// Run the steps under the chip select `cs`, which is released at the end, even on failure.
+    pub fn transaction(&self, cs: Io, operations: &mut [SpiOperation], timeout: u32) -> Result<()>
+    {
+        if !EventCenter::lock(self.spi) {
+            return Err(Error::PeripheralBusy);
+        }
+
+        cs.set_state(IoState::from(GPIO_PIN_RESET));
+
+        let result = run(self, operations, timeout);
+
+        Transaction { cs, hold_ns: 0, handle: None, chain: None }.deselect();
+        EventCenter::unlock(self.spi);
+
+        result
+    }
+
+    /// Run the steps with DMA under the chip select `cs`, the handle given to `with_event` gets
+    /// one `SpiEvent::TxRxCompleted` once all of them are done, or the event of the failed one.
+    pub fn transaction_with_dma(&self, cs: Io, operations: &'static mut [SpiOperation<'static>]) -> Result<()>
+    {
+        check_dma(operations)?;
+
+        if !EventCenter::lock(self.spi) {
+            return Err(Error::PeripheralBusy);
+        }
+
+        let transaction = Transaction {
+            cs,
+            hold_ns: 0,
+            handle: EventCenter::get(self.spi),
+            chain: Some(SpiChain::new(operations)),
+        };
+
+        cs.set_state(IoState::from(GPIO_PIN_RESET));
+        EventCenter::begin(self.spi, transaction);
+
+        if let Err(error) = EventCenter::advance(self.spi) {
+            EventCenter::cancel(self.spi);
+            transaction.deselect();
+            EventCenter::unlock(self.spi);
+            return Err(error);
+        }
+
+        Ok(())
+    }
+}
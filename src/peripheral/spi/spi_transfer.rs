@@ -0,0 +1,98 @@
+use escw_mcu::common::Error;
+use escw_mcu::common::Result;
+
+use crate::hal::spi::*;
+use crate::hal::HalStatus;
+use crate::peripheral::chunk::Chunk;
+use crate::peripheral::chunk::Chunks;
+
+use super::event::EventCenter;
+use super::SpiIdentifies;
+
+#[derive(Clone, Copy)]
+pub enum SpiDirection
+{
+    Transmit,
+    Receive,
+    TransmitReceive,
+}
+
+/// An interrupt or DMA transfer, the chunks after the first one are started by the end events.
+#[derive(Clone, Copy)]
+pub struct SpiTransfer
+{
+    direction: SpiDirection,
+    dma: bool,
+    chunks: Chunks,
+}
+
+impl SpiTransfer
+{
+    pub fn new(direction: SpiDirection, dma: bool, chunks: Chunks) -> Self
+    {
+        SpiTransfer { direction, dma, chunks }
+    }
+
+    /// Start the transfer, a long one is kept by the event center until its last chunk ends.
+    pub fn start(mut self, spi: SpiIdentifies) -> Result<()>
+    {
+        // The record of a running transfer is still used by its end events.
+        if !matches!(unsafe { HAL_SPI_GetState(spi.into()) }, SpiState::Ready) {
+            return Err(Error::PeripheralBusy);
+        }
+
+        let chunk = self.chunks.next();
+        let long = self.chunks.is_long();
+
+        if long {
+            EventCenter::follow(spi, Some(self));
+        }
+
+        let result = unsafe { self.start_chunk(spi.into(), chunk) }.ok();
+
+        if result.is_err() && long {
+            EventCenter::follow(spi, None);
+        }
+
+        result
+    }
+
+    /// Start the next chunk from the end event of the previous one, false when the transfer is done.
+    pub fn advance(&mut self, spi: SpiIdentifies) -> Result<bool>
+    {
+        if self.chunks.is_done() {
+            return Ok(false);
+        }
+
+        let chunk = self.chunks.next();
+
+        unsafe { self.start_chunk(spi.into(), chunk) }.ok().map(|_| true)
+    }
+
+    unsafe fn start_chunk(&self, hspi: &Hspi, chunk: Chunk) -> HalStatus
+    {
+        match (self.direction, self.dma) {
+            (SpiDirection::Transmit, false) => HAL_SPI_Transmit_IT(hspi, chunk.tx, chunk.size),
+            (SpiDirection::Receive, false) => HAL_SPI_Receive_IT(hspi, chunk.rx, chunk.size),
+            (SpiDirection::TransmitReceive, false) => HAL_SPI_TransmitReceive_IT(hspi, chunk.tx, chunk.rx, chunk.size),
+            (SpiDirection::Transmit, true) => HAL_SPI_Transmit_DMA(hspi, chunk.tx, chunk.size),
+            (SpiDirection::Receive, true) => HAL_SPI_Receive_DMA(hspi, chunk.rx, chunk.size),
+            (SpiDirection::TransmitReceive, true) => HAL_SPI_TransmitReceive_DMA(hspi, chunk.tx, chunk.rx, chunk.size),
+        }
+    }
+}
+
+/// Run a blocking transfer as many calls of the HAL as needed, `timeout` applies to each of them.
+pub fn run(spi: SpiIdentifies, direction: SpiDirection, chunks: Chunks, timeout: u32) -> Result<()>
+{
+    let hspi: &Hspi = spi.into();
+
+    chunks.run(|chunk| unsafe {
+        match direction {
+            SpiDirection::Transmit => HAL_SPI_Transmit(hspi, chunk.tx, chunk.size, timeout),
+            SpiDirection::Receive => HAL_SPI_Receive(hspi, chunk.rx, chunk.size, timeout),
+            SpiDirection::TransmitReceive => HAL_SPI_TransmitReceive(hspi, chunk.tx, chunk.rx, chunk.size, timeout),
+        }
+        .ok()
+    })
+}
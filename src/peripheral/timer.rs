@@ -0,0 +1,507 @@
+mod timer_capture;
+mod timer_encoder;
+mod timer_pwm;
+
+pub use timer_capture::Capture;
+pub use timer_capture::CaptureEdge;
+pub use timer_capture::CaptureEventHandle;
+pub use timer_encoder::Encoder;
+pub use timer_encoder::EncoderDirection;
+pub use timer_pwm::Pwm;
+pub use timer_pwm::PwmBreak;
+pub use timer_pwm::PwmPolarity;
+
+use escw_mcu::common::Error;
+use escw_mcu::common::Result;
+
+use crate::hal::system::HAL_RCC_GetHCLKFreq;
+use crate::hal::system::HAL_RCC_GetPCLK1Freq;
+use crate::hal::system::HAL_RCC_GetPCLK2Freq;
+use crate::hal::tim::*;
+
+/// The advanced timers TIM1 and TIM8, the general purpose and the basic timers.
+#[derive(Clone, Copy)]
+pub enum TimerIdentifies
+{
+    #[cfg(feature = "tim1")]
+    Tim1,
+    #[cfg(feature = "tim2")]
+    Tim2,
+    #[cfg(feature = "tim3")]
+    Tim3,
+    #[cfg(feature = "tim4")]
+    Tim4,
+    #[cfg(feature = "tim5")]
+    Tim5,
+    #[cfg(feature = "tim6")]
+    Tim6,
+    #[cfg(feature = "tim7")]
+    Tim7,
+    #[cfg(feature = "tim8")]
+    Tim8,
+    #[cfg(feature = "tim9")]
+    Tim9,
+    #[cfg(feature = "tim10")]
+    Tim10,
+    #[cfg(feature = "tim11")]
+    Tim11,
+    #[cfg(feature = "tim12")]
+    Tim12,
+    #[cfg(feature = "tim13")]
+    Tim13,
+    #[cfg(feature = "tim14")]
+    Tim14,
+}
+
+impl TimerIdentifies
+{
+    pub const fn count() -> usize
+    {
+        14
+    }
+}
+
+impl Into<*mut Htim> for TimerIdentifies
+{
+    fn into(self) -> *mut Htim
+    {
+        match self {
+            #[cfg(feature = "tim1")]
+            Self::Tim1 => core::ptr::addr_of_mut!(htim1),
+            #[cfg(feature = "tim2")]
+            Self::Tim2 => core::ptr::addr_of_mut!(htim2),
+            #[cfg(feature = "tim3")]
+            Self::Tim3 => core::ptr::addr_of_mut!(htim3),
+            #[cfg(feature = "tim4")]
+            Self::Tim4 => core::ptr::addr_of_mut!(htim4),
+            #[cfg(feature = "tim5")]
+            Self::Tim5 => core::ptr::addr_of_mut!(htim5),
+            #[cfg(feature = "tim6")]
+            Self::Tim6 => core::ptr::addr_of_mut!(htim6),
+            #[cfg(feature = "tim7")]
+            Self::Tim7 => core::ptr::addr_of_mut!(htim7),
+            #[cfg(feature = "tim8")]
+            Self::Tim8 => core::ptr::addr_of_mut!(htim8),
+            #[cfg(feature = "tim9")]
+            Self::Tim9 => core::ptr::addr_of_mut!(htim9),
+            #[cfg(feature = "tim10")]
+            Self::Tim10 => core::ptr::addr_of_mut!(htim10),
+            #[cfg(feature = "tim11")]
+            Self::Tim11 => core::ptr::addr_of_mut!(htim11),
+            #[cfg(feature = "tim12")]
+            Self::Tim12 => core::ptr::addr_of_mut!(htim12),
+            #[cfg(feature = "tim13")]
+            Self::Tim13 => core::ptr::addr_of_mut!(htim13),
+            #[cfg(feature = "tim14")]
+            Self::Tim14 => core::ptr::addr_of_mut!(htim14),
+        }
+    }
+}
+
+impl Into<&Htim> for TimerIdentifies
+{
+    fn into(self) -> &'static Htim
+    {
+        unsafe { &*Into::<*mut Htim>::into(self) }
+    }
+}
+
+impl TryInto<TimerIdentifies> for &Htim
+{
+    type Error = Error;
+
+    fn try_into(self) -> core::result::Result<TimerIdentifies, Self::Error>
+    {
+        match self.instance {
+            #[cfg(feature = "tim1")]
+            crate::memory::TIM1_BASE => Ok(TimerIdentifies::Tim1),
+            #[cfg(feature = "tim2")]
+            crate::memory::TIM2_BASE => Ok(TimerIdentifies::Tim2),
+            #[cfg(feature = "tim3")]
+            crate::memory::TIM3_BASE => Ok(TimerIdentifies::Tim3),
+            #[cfg(feature = "tim4")]
+            crate::memory::TIM4_BASE => Ok(TimerIdentifies::Tim4),
+            #[cfg(feature = "tim5")]
+            crate::memory::TIM5_BASE => Ok(TimerIdentifies::Tim5),
+            #[cfg(feature = "tim6")]
+            crate::memory::TIM6_BASE => Ok(TimerIdentifies::Tim6),
+            #[cfg(feature = "tim7")]
+            crate::memory::TIM7_BASE => Ok(TimerIdentifies::Tim7),
+            #[cfg(feature = "tim8")]
+            crate::memory::TIM8_BASE => Ok(TimerIdentifies::Tim8),
+            #[cfg(feature = "tim9")]
+            crate::memory::TIM9_BASE => Ok(TimerIdentifies::Tim9),
+            #[cfg(feature = "tim10")]
+            crate::memory::TIM10_BASE => Ok(TimerIdentifies::Tim10),
+            #[cfg(feature = "tim11")]
+            crate::memory::TIM11_BASE => Ok(TimerIdentifies::Tim11),
+            #[cfg(feature = "tim12")]
+            crate::memory::TIM12_BASE => Ok(TimerIdentifies::Tim12),
+            #[cfg(feature = "tim13")]
+            crate::memory::TIM13_BASE => Ok(TimerIdentifies::Tim13),
+            #[cfg(feature = "tim14")]
+            crate::memory::TIM14_BASE => Ok(TimerIdentifies::Tim14),
+            _ => Err(Error::Param),
+        }
+    }
+}
+
+/// The capture/compare channels, TIM9 and TIM12 only have the first two, TIM10, TIM11, TIM13 and TIM14
+/// only the first one.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum TimerChannel
+{
+    Channel1,
+    Channel2,
+    Channel3,
+    Channel4,
+}
+
+impl Into<u32> for TimerChannel
+{
+    fn into(self) -> u32
+    {
+        match self {
+            Self::Channel1 => TIM_CHANNEL_1,
+            Self::Channel2 => TIM_CHANNEL_2,
+            Self::Channel3 => TIM_CHANNEL_3,
+            Self::Channel4 => TIM_CHANNEL_4,
+        }
+    }
+}
+
+impl TryFrom<u32> for TimerChannel
+{
+    type Error = Error;
+
+    /// The channel of the HAL which has sent the current callback.
+    fn try_from(value: u32) -> core::result::Result<Self, Self::Error>
+    {
+        match value {
+            HAL_TIM_ACTIVE_CHANNEL_1 => Ok(Self::Channel1),
+            HAL_TIM_ACTIVE_CHANNEL_2 => Ok(Self::Channel2),
+            HAL_TIM_ACTIVE_CHANNEL_3 => Ok(Self::Channel3),
+            HAL_TIM_ACTIVE_CHANNEL_4 => Ok(Self::Channel4),
+            _ => Err(Error::Param),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum TimerEvent
+{
+    /// The counter has reached the end of its period.
+    PeriodElapsed,
+    /// The DMA has given the last duty of its buffer to the channel.
+    PulseFinished(TimerChannel),
+    /// The break input has disabled the outputs of an advanced timer.
+    Break,
+    /// The DMA has filled its buffer with the captures of the channel.
+    CaptureCompleted(TimerChannel),
+}
+
+pub type TimerEventHandle = fn(TimerEvent);
+
+pub struct Timer
+{
+    tim: TimerIdentifies,
+}
+
+impl Timer
+{
+    pub fn new(tim: TimerIdentifies) -> Self
+    {
+        Timer { tim }
+    }
+
+    pub fn with_event(&mut self, handle: TimerEventHandle)
+    {
+        event::EventCenter::set(self.tim, handle);
+    }
+
+    /// Send `TimerEvent::PeriodElapsed` `frequency` times per second, until `stop`.
+    ///
+    /// The prescaler and the period are computed from the clock of the timer, `frequency()` gives the
+    /// real frequency.
+    pub fn start(&self, frequency: u32) -> Result<()>
+    {
+        self.run(frequency, false)
+    }
+
+    /// Send `TimerEvent::PeriodElapsed` once, after one period of `frequency`, then stop the counter.
+    pub fn start_once(&self, frequency: u32) -> Result<()>
+    {
+        self.run(frequency, true)
+    }
+
+    pub fn stop(&self) -> Result<()>
+    {
+        unsafe { HAL_TIM_Base_Stop_IT(self.tim.into()).into() }
+    }
+
+    pub fn counter(&self) -> u32
+    {
+        unsafe { core::ptr::read_volatile(core::ptr::addr_of!((*self.registers()).cnt)) }
+    }
+
+    pub fn set_counter(&self, counter: u32)
+    {
+        unsafe { core::ptr::write_volatile(core::ptr::addr_of_mut!((*self.registers()).cnt), counter) }
+    }
+
+    /// Give the end of each period as the trigger output, which starts the conversions of an ADC.
+    pub fn enable_update_trigger(&self)
+    {
+        unsafe {
+            let registers = self.registers();
+            let cr2 = core::ptr::read_volatile(core::ptr::addr_of!((*registers).cr2));
+
+            core::ptr::write_volatile(core::ptr::addr_of_mut!((*registers).cr2), (cr2 & !TIM_CR2_MMS) | TIM_TRGO_UPDATE);
+        }
+    }
+
+    /// The real frequency of the periods, from the prescaler and the period of the timer.
+    pub fn frequency(&self) -> u32
+    {
+        let htim: &Htim = self.tim.into();
+
+        (self.clock() as u64 / ((htim.init.prescaler as u64 + 1) * (htim.init.period as u64 + 1))) as u32
+    }
+
+    /// The clock in Hz which drives the counter, twice the APB clock when the APB is divided.
+    pub fn clock(&self) -> u32
+    {
+        let htim: &Htim = self.tim.into();
+
+        unsafe {
+            let pclk = match htim.instance < crate::memory::APB2PERIPH_BASE {
+                true => HAL_RCC_GetPCLK1Freq(),
+                false => HAL_RCC_GetPCLK2Freq(),
+            };
+
+            match pclk == HAL_RCC_GetHCLKFreq() {
+                true => pclk,
+                false => pclk * 2,
+            }
+        }
+    }
+
+    /// TIM2 and TIM5 have 32 bits counters, the others 16 bits ones.
+    fn max_period(&self) -> u32
+    {
+        let htim: &Htim = self.tim.into();
+
+        match htim.instance {
+            crate::memory::TIM2_BASE | crate::memory::TIM5_BASE => u32::MAX,
+            _ => u16::MAX as u32,
+        }
+    }
+
+    fn registers(&self) -> *mut TimRegisters
+    {
+        let htim: &Htim = self.tim.into();
+
+        htim.instance as *mut TimRegisters
+    }
+
+    /// The compare register of a channel.
+    fn compare(&self, channel: TimerChannel) -> *mut u32
+    {
+        let registers = self.registers();
+
+        unsafe {
+            match channel {
+                TimerChannel::Channel1 => core::ptr::addr_of_mut!((*registers).ccr1),
+                TimerChannel::Channel2 => core::ptr::addr_of_mut!((*registers).ccr2),
+                TimerChannel::Channel3 => core::ptr::addr_of_mut!((*registers).ccr3),
+                TimerChannel::Channel4 => core::ptr::addr_of_mut!((*registers).ccr4),
+            }
+        }
+    }
+
+    /// TIM1 and TIM8 have complementary outputs, dead-time and a break input.
+    fn is_advanced(&self) -> bool
+    {
+        let htim: &Htim = self.tim.into();
+
+        matches!(htim.instance, crate::memory::TIM1_BASE | crate::memory::TIM8_BASE)
+    }
+
+    /// Set the prescaler and the period of an up counter for `frequency`, to be initialized by the HAL.
+    fn time_base(&self, frequency: u32) -> Result<()>
+    {
+        let (prescaler, period) = divide(self.clock(), frequency, self.max_period())?;
+
+        unsafe {
+            let htim: *mut Htim = self.tim.into();
+
+            (*htim).init.prescaler = prescaler;
+            (*htim).init.counter_mode = TIM_COUNTERMODE_UP;
+            (*htim).init.period = period;
+            (*htim).init.clock_division = TIM_CLOCKDIVISION_DIV1;
+        }
+
+        Ok(())
+    }
+
+    fn run(&self, frequency: u32, once: bool) -> Result<()>
+    {
+        unsafe {
+            let htim: *mut Htim = self.tim.into();
+            let registers = self.registers();
+
+            // Refused when the timer is not running, which is all right.
+            HAL_TIM_Base_Stop_IT(&*htim);
+
+            self.time_base(frequency)?;
+            HAL_TIM_Base_Init(&*htim).ok()?;
+
+            let cr1 = core::ptr::read_volatile(core::ptr::addr_of!((*registers).cr1));
+            let cr1 = match once {
+                true => cr1 | TIM_CR1_OPM,
+                false => cr1 & !TIM_CR1_OPM,
+            };
+
+            core::ptr::write_volatile(core::ptr::addr_of_mut!((*registers).cr1), cr1);
+
+            // The update generated by the initialization would end the first period at once.
+            core::ptr::write_volatile(core::ptr::addr_of_mut!((*registers).sr), !TIM_SR_UIF);
+
+            HAL_TIM_Base_Start_IT(&*htim).into()
+        }
+    }
+}
+
+/// The prescaler and the period of the HAL, both less one, which divide `clock` down to `frequency`.
+fn divide(clock: u32, frequency: u32, max_period: u32) -> Result<(u32, u32)>
+{
+    if frequency == 0 || frequency > clock {
+        return Err(Error::Param);
+    }
+
+    let ticks = (clock / frequency) as u64;
+    let prescaler = (ticks - 1) / (max_period as u64 + 1) + 1;
+
+    if prescaler > u16::MAX as u64 + 1 {
+        return Err(Error::Param);
+    }
+
+    Ok((prescaler as u32 - 1, (ticks / prescaler) as u32 - 1))
+}
+
+mod event
+{
+    use crate::hal::tim::*;
+
+    use super::timer_capture::CaptureContext;
+    use super::timer_encoder::EncoderContext;
+    use super::TimerChannel;
+    use super::TimerEvent;
+    use super::TimerEventHandle;
+    use super::TimerIdentifies;
+
+    static mut EVENT_CENTER: EventCenter = EventCenter::new();
+
+    pub struct EventCenter
+    {
+        handle: [Option<TimerEventHandle>; TimerIdentifies::count()],
+        capture: [Option<CaptureContext>; TimerIdentifies::count()],
+        encoder: [Option<EncoderContext>; TimerIdentifies::count()],
+    }
+
+    impl EventCenter
+    {
+        const fn new() -> Self
+        {
+            EventCenter {
+                handle: [None; TimerIdentifies::count()],
+                capture: [None; TimerIdentifies::count()],
+                encoder: [None; TimerIdentifies::count()],
+            }
+        }
+
+        pub fn set(tim: TimerIdentifies, invoke: TimerEventHandle)
+        {
+            unsafe {
+                EVENT_CENTER.handle[tim as usize] = Some(invoke);
+            }
+        }
+
+        pub fn invoke(tim: TimerIdentifies, event: TimerEvent)
+        {
+            unsafe {
+                if let Some(invoke) = EVENT_CENTER.handle[tim as usize].as_ref() {
+                    invoke(event);
+                }
+            }
+        }
+
+        /// Keep the handles and the measures of a timer in input capture.
+        pub fn set_capture(tim: TimerIdentifies, capture: Option<CaptureContext>)
+        {
+            unsafe {
+                EVENT_CENTER.capture[tim as usize] = capture;
+            }
+        }
+
+        pub fn capture(tim: TimerIdentifies) -> Option<&'static mut CaptureContext>
+        {
+            unsafe { (*core::ptr::addr_of_mut!(EVENT_CENTER.capture[tim as usize])).as_mut() }
+        }
+
+        /// Keep the wraps of a timer in encoder mode.
+        pub fn set_encoder(tim: TimerIdentifies, encoder: Option<EncoderContext>)
+        {
+            unsafe {
+                EVENT_CENTER.encoder[tim as usize] = encoder;
+            }
+        }
+
+        pub fn encoder(tim: TimerIdentifies) -> Option<&'static mut EncoderContext>
+        {
+            unsafe { (*core::ptr::addr_of_mut!(EVENT_CENTER.encoder[tim as usize])).as_mut() }
+        }
+    }
+
+    #[no_mangle]
+    pub extern "C" fn HAL_TIM_PeriodElapsedCallback(htim: &Htim)
+    {
+        if let Some(tim) = htim.try_into().ok() {
+            if let Some(capture) = EventCenter::capture(tim) {
+                capture.overflow(tim);
+            }
+
+            if let Some(encoder) = EventCenter::encoder(tim) {
+                encoder.overflow(tim);
+            }
+
+            EventCenter::invoke(tim, TimerEvent::PeriodElapsed);
+        }
+    }
+
+    #[no_mangle]
+    pub extern "C" fn HAL_TIM_PWM_PulseFinishedCallback(htim: &Htim)
+    {
+        if let (Some(tim), Ok(channel)) = (htim.try_into().ok(), TimerChannel::try_from(htim.channel)) {
+            EventCenter::invoke(tim, TimerEvent::PulseFinished(channel));
+        }
+    }
+
+    #[no_mangle]
+    pub extern "C" fn HAL_TIM_IC_CaptureCallback(htim: &Htim)
+    {
+        if let (Some(tim), Ok(channel)) = (htim.try_into().ok(), TimerChannel::try_from(htim.channel)) {
+            if let Some(capture) = EventCenter::capture(tim) {
+                capture.capture(tim, channel);
+            }
+        }
+    }
+
+    #[no_mangle]
+    pub extern "C" fn HAL_TIMEx_BreakCallback(htim: &Htim)
+    {
+        if let Some(tim) = htim.try_into().ok() {
+            EventCenter::invoke(tim, TimerEvent::Break);
+        }
+    }
+}
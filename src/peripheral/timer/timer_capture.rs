@@ -0,0 +1,362 @@
+use escw_mcu::common::Error;
+use escw_mcu::common::Result;
+
+use crate::hal::tim::*;
+
+use super::event::EventCenter;
+use super::Timer;
+use super::TimerChannel;
+use super::TimerEvent;
+use super::TimerEventHandle;
+use super::TimerIdentifies;
+
+/// The edges of the input a channel captures.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum CaptureEdge
+{
+    Rising,
+    Falling,
+    Both,
+}
+
+impl Into<u32> for CaptureEdge
+{
+    fn into(self) -> u32
+    {
+        match self {
+            Self::Rising => TIM_ICPOLARITY_RISING,
+            Self::Falling => TIM_ICPOLARITY_FALLING,
+            Self::Both => TIM_ICPOLARITY_BOTHEDGE,
+        }
+    }
+}
+
+/// Told of each capture of a channel, with the time of the edge in ticks, counting the overflows of the
+/// counter so that it wraps at 32 bits whatever the width of the counter.
+pub type CaptureEventHandle = fn(TimerChannel, u32);
+
+/// The handles and the measures of a capturing timer, kept by the event center while it runs.
+#[derive(Clone, Copy)]
+pub struct CaptureContext
+{
+    handles: [Option<CaptureEventHandle>; 4],
+    dma: [bool; 4],
+    /// The first two channels measure the input of the first one, the counter is reset at each period.
+    pwm_input: bool,
+    /// The overflows of the counter since it has started, or since the last period in PWM input mode.
+    overflows: u32,
+    /// An overflow already counted by a capture, whose update is still to come as the HAL handles the
+    /// captures first.
+    counted: bool,
+    period: Option<u32>,
+    pulse: Option<u32>,
+}
+
+impl CaptureContext
+{
+    const fn new() -> Self
+    {
+        CaptureContext {
+            handles: [None; 4],
+            dma: [false; 4],
+            pwm_input: false,
+            overflows: 0,
+            counted: false,
+            period: None,
+            pulse: None,
+        }
+    }
+
+    /// Count an overflow of the counter, a period too long for 32 bits means the input has stopped.
+    pub(super) fn overflow(&mut self, tim: TimerIdentifies)
+    {
+        if core::mem::take(&mut self.counted) {
+            return;
+        }
+
+        self.overflows = self.overflows.wrapping_add(1);
+
+        if self.pwm_input && span(tim, self.overflows, 0).is_none() {
+            self.overflows = 0;
+            self.period = None;
+            self.pulse = None;
+        }
+    }
+
+    pub(super) fn capture(&mut self, tim: TimerIdentifies, channel: TimerChannel)
+    {
+        let index = channel as usize;
+
+        if self.dma[index] {
+            EventCenter::invoke(tim, TimerEvent::CaptureCompleted(channel));
+            return;
+        }
+
+        let timer = Timer::new(tim);
+        let value = unsafe { core::ptr::read_volatile(timer.compare(channel)) };
+        let htim: &Htim = tim.into();
+
+        // An overflow still pending with a small capture has come before the edge.
+        let updated = unsafe { core::ptr::read_volatile(core::ptr::addr_of!((*timer.registers()).sr)) } & TIM_SR_UIF != 0;
+        if updated && value < htim.init.period / 2 && !self.counted {
+            self.counted = true;
+            self.overflows = self.overflows.wrapping_add(1);
+        }
+
+        let overflows = self.overflows;
+
+        let ticks = match self.pwm_input {
+            true => span(tim, overflows, value),
+            false => Some(overflows.wrapping_mul(htim.init.period.wrapping_add(1)).wrapping_add(value)),
+        };
+
+        if self.pwm_input {
+            match channel {
+                TimerChannel::Channel1 => {
+                    self.period = ticks;
+                    self.overflows = 0;
+                }
+                _ => self.pulse = ticks,
+            }
+        }
+
+        if let (Some(handle), Some(ticks)) = (self.handles[index], ticks) {
+            handle(channel, ticks);
+        }
+    }
+}
+
+/// Input capture on the channels of a timer, whose counter runs freely at a known tick frequency.
+///
+/// Each capture is given to the handle of its channel, or moved by the DMA of the channel into a buffer.
+/// `measure` rather takes the period and the duty of a PWM input on the first channel.
+pub struct Capture
+{
+    timer: Timer,
+}
+
+impl Capture
+{
+    pub fn new(tim: TimerIdentifies) -> Self
+    {
+        Capture { timer: Timer::new(tim) }
+    }
+
+    /// Take `TimerEvent::CaptureCompleted` once the DMA has filled a buffer, and `TimerEvent::PeriodElapsed`
+    /// at each overflow of the counter.
+    pub fn with_event(&mut self, handle: TimerEventHandle)
+    {
+        self.timer.with_event(handle);
+    }
+
+    pub fn timer(&self) -> &Timer
+    {
+        &self.timer
+    }
+
+    /// Initialize the timer for input capture, counting at `tick_frequency` over its whole range.
+    ///
+    /// The tick frequency is the clock of the timer divided by a whole number, `tick_frequency()` gives
+    /// the real one.
+    pub fn init(&self, tick_frequency: u32) -> Result<()>
+    {
+        let clock = self.timer.clock();
+
+        if tick_frequency == 0 || tick_frequency > clock || clock / tick_frequency > u16::MAX as u32 + 1 {
+            return Err(Error::Param);
+        }
+
+        unsafe {
+            let htim: *mut Htim = self.tim().into();
+            let registers = self.timer.registers();
+
+            (*htim).init.prescaler = clock / tick_frequency - 1;
+            (*htim).init.counter_mode = TIM_COUNTERMODE_UP;
+            (*htim).init.period = self.timer.max_period();
+            (*htim).init.clock_division = TIM_CLOCKDIVISION_DIV1;
+
+            HAL_TIM_IC_Init(&*htim).ok()?;
+
+            // Only the overflows update the timer, not the resets of the PWM input mode.
+            let cr1 = core::ptr::read_volatile(core::ptr::addr_of!((*registers).cr1));
+            core::ptr::write_volatile(core::ptr::addr_of_mut!((*registers).cr1), cr1 | TIM_CR1_URS);
+            core::ptr::write_volatile(core::ptr::addr_of_mut!((*registers).sr), !TIM_SR_UIF);
+        }
+
+        EventCenter::set_capture(self.tim(), Some(CaptureContext::new()));
+
+        Ok(())
+    }
+
+    /// The real frequency of the ticks of the counter.
+    pub fn tick_frequency(&self) -> u32
+    {
+        let htim: &Htim = self.tim().into();
+
+        self.timer.clock() / (htim.init.prescaler + 1)
+    }
+
+    /// Capture `edge` on the input of the channel, after `filter` from 0 to 15, the setting of the
+    /// digital filter of the timer.
+    pub fn configure_channel(&self, channel: TimerChannel, edge: CaptureEdge, filter: u8) -> Result<()>
+    {
+        self.configure(channel, edge, TIM_ICSELECTION_DIRECTTI, filter)
+    }
+
+    /// Send each capture of the channel to `handle`, until `stop`.
+    pub fn start(&self, channel: TimerChannel, handle: CaptureEventHandle) -> Result<()>
+    {
+        let capture = self.context()?;
+
+        capture.handles[channel as usize] = Some(handle);
+        capture.dma[channel as usize] = false;
+        self.enable_overflow(true);
+
+        unsafe { HAL_TIM_IC_Start_IT(self.tim().into(), channel.into()).into() }
+    }
+
+    pub fn stop(&self, channel: TimerChannel) -> Result<()>
+    {
+        let capture = self.context()?;
+
+        capture.handles[channel as usize] = None;
+        self.enable_overflow(capture.handles.iter().any(Option::is_some));
+
+        unsafe { HAL_TIM_IC_Stop_IT(self.tim().into(), channel.into()).into() }
+    }
+
+    /// Move each capture of the channel, the raw value of the counter, into `captures` until it is full,
+    /// which sends `TimerEvent::CaptureCompleted`. The DMA of the channel must move words.
+    pub fn start_with_dma(&self, channel: TimerChannel, captures: &'static mut [u32]) -> Result<()>
+    {
+        if captures.is_empty() || captures.len() > u16::MAX as usize {
+            return Err(Error::Param);
+        }
+
+        self.context()?.dma[channel as usize] = true;
+
+        unsafe {
+            HAL_TIM_IC_Start_DMA(self.tim().into(), channel.into(), captures.as_mut_ptr(), captures.len() as u16).into()
+        }
+    }
+
+    pub fn stop_with_dma(&self, channel: TimerChannel) -> Result<()>
+    {
+        self.context()?.dma[channel as usize] = false;
+
+        unsafe { HAL_TIM_IC_Stop_DMA(self.tim().into(), channel.into()).into() }
+    }
+
+    /// Measure the period and the pulse of a PWM on the input of the first channel: the first channel
+    /// captures its rising edges and resets the counter, the second one its falling edges.
+    ///
+    /// Both channels are taken, a period longer than 32 bits of ticks is seen as a stopped input.
+    pub fn measure(&self, filter: u8) -> Result<()>
+    {
+        self.configure(TimerChannel::Channel1, CaptureEdge::Rising, TIM_ICSELECTION_DIRECTTI, filter)?;
+        self.configure(TimerChannel::Channel2, CaptureEdge::Falling, TIM_ICSELECTION_INDIRECTTI, filter)?;
+
+        let config = TimSlaveConfig {
+            slave_mode: TIM_SLAVEMODE_RESET,
+            input_trigger: TIM_TS_TI1FP1,
+            trigger_polarity: TIM_TRIGGERPOLARITY_RISING,
+            trigger_prescaler: TIM_TRIGGERPRESCALER_DIV1,
+            trigger_filter: filter as u32,
+        };
+
+        unsafe { HAL_TIM_SlaveConfigSynchro(self.tim().into(), &config).ok()? };
+
+        let capture = self.context()?;
+
+        *capture = CaptureContext::new();
+        capture.pwm_input = true;
+        self.enable_overflow(true);
+
+        unsafe {
+            HAL_TIM_IC_Start_IT(self.tim().into(), TimerChannel::Channel1.into()).ok()?;
+            HAL_TIM_IC_Start_IT(self.tim().into(), TimerChannel::Channel2.into()).into()
+        }
+    }
+
+    /// The ticks of the last period of the PWM input, none before the second rising edge or once the
+    /// input has stopped.
+    pub fn period_ticks(&self) -> Option<u32>
+    {
+        self.context().ok()?.period
+    }
+
+    /// The ticks the PWM input has been high in the last period.
+    pub fn pulse_ticks(&self) -> Option<u32>
+    {
+        self.context().ok()?.pulse
+    }
+
+    pub fn frequency(&self) -> Option<u32>
+    {
+        match self.period_ticks()? {
+            0 => None,
+            period => Some(self.tick_frequency() / period),
+        }
+    }
+
+    /// The duty of the PWM input in percent.
+    pub fn duty(&self) -> Option<u8>
+    {
+        let (period, pulse) = (self.period_ticks()?, self.pulse_ticks()?);
+
+        match period {
+            0 => None,
+            _ => Some((pulse.min(period) as u64 * 100 / period as u64) as u8),
+        }
+    }
+
+    fn tim(&self) -> TimerIdentifies
+    {
+        self.timer.tim
+    }
+
+    fn context(&self) -> Result<&'static mut CaptureContext>
+    {
+        EventCenter::capture(self.tim()).ok_or(Error::Param)
+    }
+
+    fn configure(&self, channel: TimerChannel, edge: CaptureEdge, selection: u32, filter: u8) -> Result<()>
+    {
+        if filter > 0xF {
+            return Err(Error::Param);
+        }
+
+        let init = TimIcInit {
+            ic_polarity: edge.into(),
+            ic_selection: selection,
+            ic_prescaler: TIM_ICPSC_DIV1,
+            ic_filter: filter as u32,
+        };
+
+        unsafe { HAL_TIM_IC_ConfigChannel(self.tim().into(), &init, channel.into()).into() }
+    }
+
+    /// The update interrupt counts the overflows, the HAL only enables the interrupts of the channels.
+    fn enable_overflow(&self, enable: bool)
+    {
+        unsafe {
+            let registers = self.timer.registers();
+            let dier = core::ptr::read_volatile(core::ptr::addr_of!((*registers).dier));
+            let dier = match enable {
+                true => dier | TIM_DIER_UIE,
+                false => dier & !TIM_DIER_UIE,
+            };
+
+            core::ptr::write_volatile(core::ptr::addr_of_mut!((*registers).dier), dier);
+        }
+    }
+}
+
+/// The ticks from the reset of the counter to `value` after `overflows`, none beyond 32 bits.
+fn span(tim: TimerIdentifies, overflows: u32, value: u32) -> Option<u32>
+{
+    let htim: &Htim = tim.into();
+    let ticks = overflows as u64 * (htim.init.period as u64 + 1) + value as u64;
+
+    ticks.try_into().ok()
+}
@@ -0,0 +1,266 @@
+use escw_mcu::common::Error;
+use escw_mcu::common::Result;
+
+use crate::hal::system::HAL_GetTick;
+use crate::hal::tim::*;
+use crate::peripheral::io::Io;
+use crate::peripheral::io::IoPin;
+
+use super::event::EventCenter;
+use super::Timer;
+use super::TimerIdentifies;
+
+/// The velocity window of a new encoder, in ms.
+const DEFAULT_VELOCITY_WINDOW: u32 = 100;
+
+/// The direction the counter last moved in, forward counts up.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum EncoderDirection
+{
+    Forward,
+    Backward,
+}
+
+/// The wraps of a 16 bits counter and the positions dropped by the index, kept by the event center.
+#[derive(Clone, Copy)]
+pub struct EncoderContext
+{
+    wraps: i32,
+    /// The sum of the positions reset by the index, for the velocity to go on across a reset.
+    shift: i32,
+}
+
+impl EncoderContext
+{
+    const fn new() -> Self
+    {
+        EncoderContext { wraps: 0, shift: 0 }
+    }
+
+    /// Count a wrap of the counter, up when it has wrapped to the bottom of its range, down when to the top.
+    pub(super) fn overflow(&mut self, tim: TimerIdentifies)
+    {
+        let htim: &Htim = tim.into();
+
+        self.wraps = match Timer::new(tim).counter() < htim.init.period / 2 {
+            true => self.wraps.wrapping_add(1),
+            false => self.wraps.wrapping_sub(1),
+        };
+    }
+}
+
+/// A quadrature encoder on the first two channels of TIM1 to TIM5 or TIM8, counting the four edges of
+/// each step.
+///
+/// The position is signed on 32 bits, the 16 bits counters are extended by their update interrupt. An
+/// index pulse on an `Io`, whose rising edge interrupt is forwarded to `on_io_event`, resets it.
+pub struct Encoder
+{
+    timer: Timer,
+    index: Option<Io>,
+    /// The length of the velocity window in ms, the start of the running one and the position at its
+    /// start, and the velocity of the last one.
+    window: u32,
+    start: (u32, i32),
+    velocity: i32,
+}
+
+impl Encoder
+{
+    pub fn new(tim: TimerIdentifies) -> Self
+    {
+        Encoder {
+            timer: Timer::new(tim),
+            index: None,
+            window: DEFAULT_VELOCITY_WINDOW,
+            start: (0, 0),
+            velocity: 0,
+        }
+    }
+
+    pub fn timer(&self) -> &Timer
+    {
+        &self.timer
+    }
+
+    /// Reset the position at each pulse of `index`.
+    pub fn with_index(&mut self, index: Io)
+    {
+        self.index = Some(index);
+    }
+
+    /// Estimate the velocity over `window` ms, 100 ms by default.
+    pub fn with_velocity_window(&mut self, window: u32) -> Result<()>
+    {
+        if window == 0 {
+            return Err(Error::Param);
+        }
+
+        self.window = window;
+
+        Ok(())
+    }
+
+    /// Initialize the timer as an encoder, both inputs filtered by `filter` from 0 to 15, the setting of
+    /// the digital filter of the timer. `reversed` counts forward when the second input leads.
+    pub fn init(&self, filter: u8, reversed: bool) -> Result<()>
+    {
+        let htim: &Htim = self.timer.tim.into();
+
+        if filter > 0xF || !has_encoder(htim.instance) {
+            return Err(Error::Param);
+        }
+
+        let config = TimEncoderInit {
+            encoder_mode: TIM_ENCODERMODE_TI12,
+            ic1_polarity: TIM_ICPOLARITY_RISING,
+            ic1_selection: TIM_ICSELECTION_DIRECTTI,
+            ic1_prescaler: TIM_ICPSC_DIV1,
+            ic1_filter: filter as u32,
+            ic2_polarity: match reversed {
+                true => TIM_ICPOLARITY_FALLING,
+                false => TIM_ICPOLARITY_RISING,
+            },
+            ic2_selection: TIM_ICSELECTION_DIRECTTI,
+            ic2_prescaler: TIM_ICPSC_DIV1,
+            ic2_filter: filter as u32,
+        };
+
+        unsafe {
+            let htim: *mut Htim = self.timer.tim.into();
+
+            (*htim).init.prescaler = 0;
+            (*htim).init.counter_mode = TIM_COUNTERMODE_UP;
+            (*htim).init.period = self.timer.max_period();
+            (*htim).init.clock_division = TIM_CLOCKDIVISION_DIV1;
+
+            HAL_TIM_Encoder_Init(&*htim, &config).ok()?;
+
+            let registers = self.timer.registers();
+            core::ptr::write_volatile(core::ptr::addr_of_mut!((*registers).sr), !TIM_SR_UIF);
+        }
+
+        EventCenter::set_encoder(self.timer.tim, Some(EncoderContext::new()));
+
+        Ok(())
+    }
+
+    /// Start counting from the position 0.
+    pub fn start(&mut self) -> Result<()>
+    {
+        self.timer.set_counter(0);
+        EventCenter::set_encoder(self.timer.tim, Some(EncoderContext::new()));
+        self.start = (unsafe { HAL_GetTick() }, 0);
+        self.velocity = 0;
+
+        unsafe {
+            let registers = self.timer.registers();
+
+            // The 32 bits counters do not need to be extended.
+            if self.timer.max_period() != u32::MAX {
+                let dier = core::ptr::read_volatile(core::ptr::addr_of!((*registers).dier));
+                core::ptr::write_volatile(core::ptr::addr_of_mut!((*registers).dier), dier | TIM_DIER_UIE);
+            }
+
+            HAL_TIM_Encoder_Start(self.timer.tim.into(), TIM_CHANNEL_ALL).into()
+        }
+    }
+
+    pub fn stop(&self) -> Result<()>
+    {
+        unsafe {
+            let registers = self.timer.registers();
+            let dier = core::ptr::read_volatile(core::ptr::addr_of!((*registers).dier));
+            core::ptr::write_volatile(core::ptr::addr_of_mut!((*registers).dier), dier & !TIM_DIER_UIE);
+
+            HAL_TIM_Encoder_Stop(self.timer.tim.into(), TIM_CHANNEL_ALL).into()
+        }
+    }
+
+    /// The position in edges of the inputs since the start or the last index pulse, wrapping at 32 bits.
+    pub fn position(&self) -> i32
+    {
+        let context = match EventCenter::encoder(self.timer.tim) {
+            Some(context) => context as *const EncoderContext,
+            None => return self.timer.counter() as i32,
+        };
+
+        let htim: &Htim = self.timer.tim.into();
+        let range = htim.init.period.wrapping_add(1) as i32;
+
+        // Read the wraps again until no update has come between them and the counter.
+        loop {
+            let wraps = unsafe { core::ptr::read_volatile(core::ptr::addr_of!((*context).wraps)) };
+            let counter = self.timer.counter();
+
+            if wraps == unsafe { core::ptr::read_volatile(core::ptr::addr_of!((*context).wraps)) } {
+                return wraps.wrapping_mul(range).wrapping_add(counter as i32);
+            }
+        }
+    }
+
+    /// Set the position 0 at the current one.
+    pub fn reset(&self)
+    {
+        if let Some(context) = EventCenter::encoder(self.timer.tim) {
+            let position = self.position();
+
+            self.timer.set_counter(0);
+            context.wraps = 0;
+            context.shift = context.shift.wrapping_add(position);
+        }
+    }
+
+    pub fn direction(&self) -> EncoderDirection
+    {
+        let cr1 = unsafe { core::ptr::read_volatile(core::ptr::addr_of!((*self.timer.registers()).cr1)) };
+
+        match cr1 & TIM_CR1_DIR {
+            0 => EncoderDirection::Forward,
+            _ => EncoderDirection::Backward,
+        }
+    }
+
+    /// Reset the position on the pulse of the index.
+    pub fn on_io_event(&self, pin: IoPin)
+    {
+        if let Some(index) = self.index {
+            if Into::<u16>::into(pin) == Into::<u16>::into(index.pin()) {
+                self.reset();
+            }
+        }
+    }
+
+    /// The velocity in edges per second over the last whole window, which is ended by the first call
+    /// after it. Call it at least once per window from the main loop.
+    pub fn velocity(&mut self) -> i32
+    {
+        let now = unsafe { HAL_GetTick() };
+        let elapsed = now.wrapping_sub(self.start.0);
+
+        if elapsed >= self.window {
+            let shift = EventCenter::encoder(self.timer.tim).map_or(0, |context| context.shift);
+            let count = self.position().wrapping_add(shift);
+            let moved = count.wrapping_sub(self.start.1) as i64;
+
+            self.velocity = (moved * 1000 / elapsed as i64) as i32;
+            self.start = (now, count);
+        }
+
+        self.velocity
+    }
+}
+
+/// TIM1 to TIM5 and TIM8 have the encoder mode.
+fn has_encoder(instance: u32) -> bool
+{
+    matches!(
+        instance,
+        crate::memory::TIM1_BASE
+            | crate::memory::TIM2_BASE
+            | crate::memory::TIM3_BASE
+            | crate::memory::TIM4_BASE
+            | crate::memory::TIM5_BASE
+            | crate::memory::TIM8_BASE
+    )
+}
@@ -0,0 +1,254 @@
+use escw_mcu::common::Error;
+use escw_mcu::common::Result;
+
+use crate::hal::tim::*;
+
+use super::Timer;
+use super::TimerChannel;
+use super::TimerEventHandle;
+use super::TimerIdentifies;
+
+/// The largest dead-time generator setting, 1008 clocks of the timer.
+const MAX_DEAD_TIME: u64 = 1008;
+
+/// The level of an output while the counter is below the duty.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum PwmPolarity
+{
+    ActiveHigh,
+    ActiveLow,
+}
+
+/// The break input of an advanced timer, which disables its outputs as soon as it is active.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct PwmBreak
+{
+    pub polarity: PwmPolarity,
+    /// Enable the outputs again at the next period once the break input is released, rather than by
+    /// `Pwm::resume`.
+    pub automatic_output: bool,
+}
+
+/// PWM on the channels of a timer, counting up, the output is active while the counter is below the duty.
+///
+/// The duty is given in ticks of the timer, from 0 to `period()`, or in percent of the period.
+pub struct Pwm
+{
+    timer: Timer,
+}
+
+impl Pwm
+{
+    pub fn new(tim: TimerIdentifies) -> Self
+    {
+        Pwm { timer: Timer::new(tim) }
+    }
+
+    /// Take `TimerEvent::PulseFinished` at the end of a DMA stream, and `TimerEvent::Break`.
+    pub fn with_event(&mut self, handle: TimerEventHandle)
+    {
+        self.timer.with_event(handle);
+    }
+
+    pub fn timer(&self) -> &Timer
+    {
+        &self.timer
+    }
+
+    /// Initialize the timer for PWM at `frequency`, the prescaler and the period are computed from its clock.
+    pub fn init(&self, frequency: u32) -> Result<()>
+    {
+        self.timer.time_base(frequency)?;
+
+        unsafe { HAL_TIM_PWM_Init(self.timer.tim.into()).into() }
+    }
+
+    /// Set a channel in PWM mode with a duty of 0, `complementary` is the polarity of its complementary
+    /// output on an advanced timer.
+    pub fn configure_channel(&self, channel: TimerChannel, polarity: PwmPolarity, complementary: PwmPolarity) -> Result<()>
+    {
+        let init = TimOcInit {
+            oc_mode: TIM_OCMODE_PWM1,
+            pulse: 0,
+            oc_polarity: match polarity {
+                PwmPolarity::ActiveHigh => TIM_OCPOLARITY_HIGH,
+                PwmPolarity::ActiveLow => TIM_OCPOLARITY_LOW,
+            },
+            ocn_polarity: match complementary {
+                PwmPolarity::ActiveHigh => TIM_OCNPOLARITY_HIGH,
+                PwmPolarity::ActiveLow => TIM_OCNPOLARITY_LOW,
+            },
+            oc_fast_mode: TIM_OCFAST_DISABLE,
+            oc_idle_state: TIM_OCIDLESTATE_RESET,
+            ocn_idle_state: TIM_OCNIDLESTATE_RESET,
+        };
+
+        unsafe { HAL_TIM_PWM_ConfigChannel(self.timer.tim.into(), &init, channel.into()).into() }
+    }
+
+    pub fn start(&self, channel: TimerChannel) -> Result<()>
+    {
+        unsafe { HAL_TIM_PWM_Start(self.timer.tim.into(), channel.into()).into() }
+    }
+
+    pub fn stop(&self, channel: TimerChannel) -> Result<()>
+    {
+        unsafe { HAL_TIM_PWM_Stop(self.timer.tim.into(), channel.into()).into() }
+    }
+
+    /// Start the complementary output of a channel, only the first three channels of TIM1 and TIM8 have one.
+    pub fn start_complementary(&self, channel: TimerChannel) -> Result<()>
+    {
+        self.check_complementary(channel)?;
+
+        unsafe { HAL_TIMEx_PWMN_Start(self.timer.tim.into(), channel.into()).into() }
+    }
+
+    pub fn stop_complementary(&self, channel: TimerChannel) -> Result<()>
+    {
+        self.check_complementary(channel)?;
+
+        unsafe { HAL_TIMEx_PWMN_Stop(self.timer.tim.into(), channel.into()).into() }
+    }
+
+    /// The ticks of one period, the duty of an output always active.
+    pub fn period(&self) -> u32
+    {
+        let htim: &Htim = self.timer.tim.into();
+
+        htim.init.period.saturating_add(1)
+    }
+
+    pub fn set_duty(&self, channel: TimerChannel, percent: u8) -> Result<()>
+    {
+        if percent > 100 {
+            return Err(Error::Param);
+        }
+
+        self.set_duty_ticks(channel, (self.period() as u64 * percent as u64 / 100) as u32)
+    }
+
+    /// Set the duty in ticks, taken at the next period.
+    pub fn set_duty_ticks(&self, channel: TimerChannel, ticks: u32) -> Result<()>
+    {
+        if ticks > self.period() {
+            return Err(Error::Param);
+        }
+
+        unsafe { core::ptr::write_volatile(self.timer.compare(channel), ticks) };
+
+        Ok(())
+    }
+
+    pub fn duty_ticks(&self, channel: TimerChannel) -> u32
+    {
+        unsafe { core::ptr::read_volatile(self.timer.compare(channel)) }
+    }
+
+    /// Set the dead-time between an output and its complementary one, and the break input, of TIM1 or TIM8.
+    ///
+    /// The dead-time is rounded down to what the generator takes, up to 1008 clocks of the timer.
+    pub fn configure_dead_time(&self, dead_time_ns: u32, break_input: Option<PwmBreak>) -> Result<()>
+    {
+        if !self.timer.is_advanced() {
+            return Err(Error::Param);
+        }
+
+        let config = TimBreakDeadTimeConfig {
+            off_state_run_mode: TIM_OSSR_ENABLE,
+            off_state_idle_mode: TIM_OSSI_ENABLE,
+            lock_level: TIM_LOCKLEVEL_OFF,
+            dead_time: dead_time(self.timer.clock(), dead_time_ns)?,
+            break_state: match break_input {
+                Some(_) => TIM_BREAK_ENABLE,
+                None => TIM_BREAK_DISABLE,
+            },
+            break_polarity: match break_input.map(|break_input| break_input.polarity) {
+                Some(PwmPolarity::ActiveHigh) => TIM_BREAKPOLARITY_HIGH,
+                _ => TIM_BREAKPOLARITY_LOW,
+            },
+            break_filter: 0,
+            automatic_output: match break_input.map(|break_input| break_input.automatic_output) {
+                Some(true) => TIM_AUTOMATICOUTPUT_ENABLE,
+                _ => TIM_AUTOMATICOUTPUT_DISABLE,
+            },
+        };
+
+        unsafe {
+            HAL_TIMEx_ConfigBreakDeadTime(self.timer.tim.into(), &config).ok()?;
+
+            // The HAL only sets the break, its interrupt sends `TimerEvent::Break`.
+            let registers = self.timer.registers();
+            let dier = core::ptr::read_volatile(core::ptr::addr_of!((*registers).dier));
+            let dier = match break_input {
+                Some(_) => dier | TIM_DIER_BIE,
+                None => dier & !TIM_DIER_BIE,
+            };
+
+            core::ptr::write_volatile(core::ptr::addr_of_mut!((*registers).dier), dier);
+        }
+
+        Ok(())
+    }
+
+    /// Enable the outputs of an advanced timer again after a break, once the break input is released.
+    pub fn resume(&self) -> Result<()>
+    {
+        if !self.timer.is_advanced() {
+            return Err(Error::Param);
+        }
+
+        unsafe {
+            let registers = self.timer.registers();
+            let bdtr = core::ptr::read_volatile(core::ptr::addr_of!((*registers).bdtr));
+
+            core::ptr::write_volatile(core::ptr::addr_of_mut!((*registers).bdtr), bdtr | TIM_BDTR_MOE);
+        }
+
+        Ok(())
+    }
+
+    /// Give the channel a new duty at each period from `duties`, in ticks, as for the bits of a WS2812 stream.
+    ///
+    /// The DMA of the channel must move words, `TimerEvent::PulseFinished` tells the last duty has been taken.
+    /// The output keeps the last duty until `stop_with_dma`, a stream usually ends with a duty of 0.
+    pub fn start_with_dma(&self, channel: TimerChannel, duties: &'static [u32]) -> Result<()>
+    {
+        if duties.is_empty() || duties.len() > u16::MAX as usize {
+            return Err(Error::Param);
+        }
+
+        unsafe { HAL_TIM_PWM_Start_DMA(self.timer.tim.into(), channel.into(), duties.as_ptr(), duties.len() as u16).into() }
+    }
+
+    pub fn stop_with_dma(&self, channel: TimerChannel) -> Result<()>
+    {
+        unsafe { HAL_TIM_PWM_Stop_DMA(self.timer.tim.into(), channel.into()).into() }
+    }
+
+    fn check_complementary(&self, channel: TimerChannel) -> Result<()>
+    {
+        if !self.timer.is_advanced() || channel == TimerChannel::Channel4 {
+            return Err(Error::Param);
+        }
+
+        Ok(())
+    }
+}
+
+/// The setting of the dead-time generator for `dead_time_ns`, in clocks of the timer as the dead-time
+/// generator is not divided: up to 127 clocks one by one, then by steps of 2, 8 and 16 clocks.
+fn dead_time(clock: u32, dead_time_ns: u32) -> Result<u32>
+{
+    let clocks = clock as u64 * dead_time_ns as u64 / 1_000_000_000;
+
+    let setting = match clocks {
+        0..=127 => clocks,
+        128..=255 => 0x80 | (clocks / 2 - 64),
+        256..=511 => 0xC0 | (clocks / 8 - 32),
+        512..=MAX_DEAD_TIME => 0xE0 | (clocks / 16 - 32),
+        _ => return Err(Error::Param),
+    };
+
+    Ok(setting as u32)
+}
@@ -1,12 +1,27 @@
+pub mod lin;
+pub mod modbus;
+
+mod uart_context;
 mod uart_event;
 
+pub use uart_context::UartErrorCount;
+pub use uart_event::UartError;
 pub use uart_event::UartEvent;
 
+use core::ptr::null;
+
+use escw_mcu::common::Error;
 use escw_mcu::common::Result;
 use escw_mcu::peripheral::uart::UartDevice;
 
 use crate::hal::uart::*;
 use crate::hal::HalStatus;
+use crate::peripheral::chunk::Chunks;
+use crate::peripheral::chunk::CHUNK_SIZE;
+
+use uart_context::ContextCenter;
+use uart_context::UartReception;
+use uart_context::UartTransmission;
 
 static mut EVENT_HANDLE: Option<fn(&mut UartHandle, UartEvent)> = None;
 
@@ -21,6 +36,151 @@ impl Uart
     {
         Uart { uart }
     }
+
+    /// Receive exactly `data.len()` bytes, an idle line does not end the reception.
+    pub fn receive_fixed(&self, data: &mut [u8], timeout: u32) -> Result<()>
+    {
+        Chunks::new(null(), data.as_ptr(), data.len(), 1).run(|chunk| unsafe { HAL_UART_Receive(self.uart, chunk.rx, chunk.size, timeout).ok() })
+    }
+
+    /// Receive exactly `data.len()` bytes, `UartEvent::RxCompleted` is sent when all of them arrived.
+    pub fn receive_fixed_async_int(&self, data: &mut [u8]) -> Result<()>
+    {
+        self.start_reception(UartReception::Interrupt(data.as_ptr(), data.len()))
+    }
+
+    /// Receive exactly `data.len()` bytes, `UartEvent::RxHalf` and `UartEvent::RxCompleted` are sent
+    /// when the first half and all of them arrived.
+    pub fn receive_fixed_async_dma(&self, data: &mut [u8]) -> Result<()>
+    {
+        self.start_reception(UartReception::Dma(data.as_ptr(), data.len()))
+    }
+
+    /// Receive into `data` endlessly, `UartEvent::RxHalf` and `UartEvent::RxCompleted` are sent each time
+    /// the first and the second half of it are filled, until the reception is aborted.
+    ///
+    /// The reception is started again on completion if the DMA stream is not configured in circular mode.
+    /// The buffer is at most 65535 bytes, as the DMA stream cannot go on to a next chunk by itself.
+    pub fn receive_fixed_async_dma_circular(&self, data: &mut [u8]) -> Result<()>
+    {
+        if data.len() > CHUNK_SIZE
+        {
+            return Err(Error::Param);
+        }
+
+        self.start_reception(UartReception::CircularDma(data.as_ptr(), data.len()))
+    }
+
+    /// Start the last asynchronous reception again on the same buffer when an error has aborted it.
+    ///
+    /// Overrun errors, and any error during a DMA reception, make the HAL abort the reception.
+    pub fn with_auto_restart(&self, enable: bool)
+    {
+        if let Some(context) = ContextCenter::get(self.uart)
+        {
+            context.auto_restart = enable;
+        }
+    }
+
+    pub fn error_count(&self) -> UartErrorCount
+    {
+        ContextCenter::get(self.uart).map_or(UartErrorCount::default(), |context| context.errors)
+    }
+
+    pub fn clear_error_count(&self)
+    {
+        if let Some(context) = ContextCenter::get(self.uart)
+        {
+            context.errors = UartErrorCount::default();
+        }
+    }
+
+    /// Whether a reception is going on, an error which aborted it makes it false.
+    pub fn is_receiving(&self) -> bool
+    {
+        unsafe { receiving(self.uart) }
+    }
+
+    fn start_reception(&self, reception: UartReception) -> Result<()>
+    {
+        // The context of a running reception is still used by its callbacks.
+        if unsafe { receiving(self.uart) }
+        {
+            return Err(Error::PeripheralBusy);
+        }
+
+        let context = match ContextCenter::get(self.uart)
+        {
+            Some(context) => context,
+            // The callbacks cannot go on to the next chunks without a context.
+            None if reception.is_long() => return Err(Error::Param),
+            None => return unsafe { reception.start(self.uart).into() },
+        };
+
+        context.reception = Some(reception);
+        context.received = 0;
+
+        let state = unsafe { reception.start(self.uart) };
+
+        if !matches!(state, HalStatus::Ok)
+        {
+            context.reception = None;
+        }
+
+        state.into()
+    }
+
+    /// Start an interrupt or DMA transmission, only its last chunk sends `UartEvent::TxCompleted`.
+    fn start_transmission(&self, data: &[u8], dma: bool) -> Result<()>
+    {
+        // The record of a running transmission is still used by its end events.
+        if unsafe { transmitting(self.uart) }
+        {
+            return Err(Error::PeripheralBusy);
+        }
+
+        let mut transmission = UartTransmission {
+            dma,
+            chunks: Chunks::new(data.as_ptr(), null(), data.len(), 1),
+        };
+
+        let chunk = transmission.chunks.next();
+        let long = transmission.chunks.is_long();
+
+        match ContextCenter::get(self.uart)
+        {
+            Some(context) => context.transmission = long.then_some(transmission),
+            None if long => return Err(Error::Param),
+            None =>
+            {}
+        }
+
+        let state = unsafe {
+            match dma
+            {
+                true => HAL_UART_Transmit_DMA(self.uart, chunk.tx, chunk.size),
+                false => HAL_UART_Transmit_IT(self.uart, chunk.tx, chunk.size),
+            }
+        };
+
+        if !matches!(state, HalStatus::Ok) && long
+        {
+            if let Some(context) = ContextCenter::get(self.uart)
+            {
+                context.transmission = None;
+            }
+        }
+
+        state.into()
+    }
+
+    fn stop_reception(&self)
+    {
+        if let Some(context) = ContextCenter::get(self.uart)
+        {
+            context.reception = None;
+        }
+    }
 }
 
 impl UartDevice for Uart
@@ -38,46 +198,58 @@ impl UartDevice for Uart
 
     fn transmit(&self, data: &[u8], timeout: u32) -> Result<()>
     {
-        unsafe { HAL_UART_Transmit(self.uart, data.as_ptr(), data.len() as u16, timeout).into() }
+        Chunks::new(data.as_ptr(), null(), data.len(), 1).run(|chunk| unsafe { HAL_UART_Transmit(self.uart, chunk.tx, chunk.size, timeout).ok() })
     }
 
+    /// A chunk filled up without an idle line goes on with the next one, `timeout` applies to each chunk.
     fn receive(&self, data: &mut [u8], timeout: u32) -> Result<u32>
     {
-        let mut size: u16 = 0;
+        let mut chunks = Chunks::new(null(), data.as_ptr(), data.len(), 1);
 
-        unsafe {
-            let state = HAL_UARTEx_ReceiveToIdle(self.uart, data.as_ptr(), data.len() as u16, &mut size, timeout);
+        loop
+        {
+            let chunk = chunks.next();
+            let mut size: u16 = 0;
+
+            unsafe {
+                let state = HAL_UARTEx_ReceiveToIdle(self.uart, chunk.rx, chunk.size, &mut size, timeout);
+
+                if !matches!(state, HalStatus::Ok)
+                {
+                    return Err(state.into());
+                }
+            }
 
-            match state
+            if size < chunk.size || chunks.is_done()
             {
-                HalStatus::Ok => Ok(size as u32),
-                _ => Err(state.into()),
+                return Ok((chunk.offset + size as usize) as u32);
             }
         }
     }
 
     fn transmit_async_int(&self, data: &[u8]) -> Result<()>
     {
-        unsafe { HAL_UART_Transmit_IT(self.uart, data.as_ptr(), data.len() as u16).into() }
+        self.start_transmission(data, false)
     }
 
     fn receive_async_int(&self, data: &mut [u8]) -> Result<()>
     {
-        unsafe { HAL_UARTEx_ReceiveToIdle_IT(self.uart, data.as_ptr(), data.len() as u16).into() }
+        self.start_reception(UartReception::ToIdleInterrupt(data.as_ptr(), data.len()))
     }
 
     fn transmit_async_dma(&self, data: &[u8]) -> Result<()>
     {
-        unsafe { HAL_UART_Transmit_DMA(self.uart, data.as_ptr(), data.len() as u16).into() }
+        self.start_transmission(data, true)
     }
 
     fn receive_async_dma(&self, data: &mut [u8]) -> Result<()>
     {
-        unsafe { HAL_UARTEx_ReceiveToIdle_DMA(self.uart, data.as_ptr(), data.len() as u16).into() }
+        self.start_reception(UartReception::ToIdleDma(data.as_ptr(), data.len()))
     }
 
     fn abort(&self) -> Result<()>
     {
+        self.stop_reception();
         unsafe { HAL_UART_Abort_IT(self.uart).into() }
     }
 
@@ -88,6 +260,7 @@ impl UartDevice for Uart
 
     fn abort_receive(&self) -> Result<()>
     {
+        self.stop_reception();
         unsafe { HAL_UART_AbortReceive_IT(self.uart).into() }
     }
 }
@@ -95,6 +268,19 @@ impl UartDevice for Uart
 #[no_mangle]
 pub unsafe extern "C" fn HAL_UART_TxCpltCallback(uart: *mut UartHandle)
 {
+    if let Some(context) = ContextCenter::get(uart)
+    {
+        if let Some(transmission) = context.transmission.as_mut()
+        {
+            if transmission.advance(uart)
+            {
+                return;
+            }
+
+            context.transmission = None;
+        }
+    }
+
     if let Some(event_handle) = EVENT_HANDLE
     {
         event_handle(uart.as_mut().unwrap_unchecked(), UartEvent::TxCompleted);
@@ -104,32 +290,99 @@ pub unsafe extern "C" fn HAL_UART_TxCpltCallback(uart: *mut UartHandle)
 #[no_mangle]
 pub unsafe extern "C" fn HAL_UART_TxHalfCpltCallback(uart: *mut UartHandle)
 {
+    // The half of a chunk is not the half of a long transmission.
+    if ContextCenter::get(uart).is_some_and(|context| context.transmission.is_some())
+    {
+        return;
+    }
+
     if let Some(event_handle) = EVENT_HANDLE
     {
         event_handle(uart.as_mut().unwrap_unchecked(), UartEvent::TxHalf);
     }
 }
 
-// #[no_mangle]
-// pub extern "C" fn HAL_UART_RxCpltCallback(huart: &Huart) {
-//     if let Some(uart) = huart.try_into().ok() {
-//         EventCenter::invoke(uart, UartEvent::RxCompleted);
-//     }
-// }
+#[no_mangle]
+pub unsafe extern "C" fn HAL_UART_RxCpltCallback(uart: *mut UartHandle)
+{
+    let mut received = 0;
+
+    if let Some(context) = ContextCenter::get(uart)
+    {
+        match context.reception
+        {
+            // Restart at once, a DMA stream in circular mode is still running and refuses it.
+            Some(reception @ UartReception::CircularDma(..)) =>
+            {
+                reception.start(uart);
+                received = reception.size();
+            }
+            Some(reception) =>
+            {
+                received = context.received + reception.chunk_size(context.received);
+
+                if received < reception.size() && reception.start_at(uart, received).ok().is_ok()
+                {
+                    context.received = received;
+                    return;
+                }
+
+                // A finished reception is not started again by a later error.
+                context.reception = None;
+                context.received = 0;
+            }
+            None =>
+            {}
+        }
+    }
+
+    if let Some(event_handle) = EVENT_HANDLE
+    {
+        event_handle(uart.as_mut().unwrap_unchecked(), UartEvent::RxCompleted(received as u32));
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn HAL_UART_RxHalfCpltCallback(uart: *mut UartHandle)
+{
+    if long_reception(uart)
+    {
+        return;
+    }
 
-// #[no_mangle]
-// pub extern "C" fn HAL_UART_RxHalfCpltCallback(huart: &Huart) {
-//     if let Some(uart) = huart.try_into().ok() {
-//         EventCenter::invoke(uart, UartEvent::RxHalf);
-//     }
-// }
+    if let Some(event_handle) = EVENT_HANDLE
+    {
+        event_handle(uart.as_mut().unwrap_unchecked(), UartEvent::RxHalf);
+    }
+}
 
 #[no_mangle]
 pub unsafe extern "C" fn HAL_UART_ErrorCallback(uart: *mut UartHandle)
 {
+    let error = UartError::from(HAL_UART_GetError(uart));
+
+    if let Some(context) = ContextCenter::get(uart)
+    {
+        context.errors.count(error);
+
+        // Only a reception still recorded has been aborted by the error, a finished one is forgotten.
+        if context.auto_restart && !receiving(uart)
+        {
+            if let Some(reception) = context.reception
+            {
+                context.received = 0;
+
+                if !matches!(reception.start(uart), HalStatus::Ok)
+                {
+                    context.reception = None;
+                }
+            }
+        }
+    }
+
     if let Some(event_handle) = EVENT_HANDLE
     {
-        event_handle(uart.as_mut().unwrap_unchecked(), UartEvent::Error);
+        event_handle(uart.as_mut().unwrap_unchecked(), UartEvent::Error(error));
     }
 }
 
@@ -163,15 +416,56 @@ pub unsafe extern "C" fn HAL_UART_AbortReceiveCpltCallback(uart: *mut UartHandle
 #[no_mangle]
 pub unsafe extern "C" fn HAL_UARTEx_RxEventCallback(uart: *mut UartHandle, size: u16)
 {
-    let mut state = UartEvent::RxCompleted(size);
+    let mut state = UartEvent::RxCompleted(size as u32);
 
     if HAL_UART_GetState(uart) == State::BusyRx
     {
+        if long_reception(uart)
+        {
+            return;
+        }
+
         state = UartEvent::RxHalf;
     }
+    else if let Some(context) = ContextCenter::get(uart)
+    {
+        // A chunk filled up without an idle line, the reception goes on with the next one.
+        if let Some(reception) = context.reception
+        {
+            let received = context.received + size as usize;
+
+            if size as usize == reception.chunk_size(context.received)
+                && received < reception.size()
+                && reception.start_at(uart, received).ok().is_ok()
+            {
+                context.received = received;
+                return;
+            }
+
+            context.reception = None;
+            context.received = 0;
+            state = UartEvent::RxCompleted(received as u32);
+        }
+    }
 
     if let Some(event_handle) = EVENT_HANDLE
     {
         event_handle(uart.as_mut().unwrap_unchecked(), state);
     }
 }
+
+unsafe fn receiving(uart: *mut UartHandle) -> bool
+{
+    matches!(HAL_UART_GetState(uart), State::BusyRx | State::BusyTxRx)
+}
+
+unsafe fn transmitting(uart: *mut UartHandle) -> bool
+{
+    matches!(HAL_UART_GetState(uart), State::BusyTx | State::BusyTxRx)
+}
+
+/// The half of a chunk is not the half of a long reception.
+fn long_reception(uart: *mut UartHandle) -> bool
+{
+    ContextCenter::get(uart).is_some_and(|context| context.reception.is_some_and(|reception| reception.is_long()))
+}
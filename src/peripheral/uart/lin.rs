@@ -0,0 +1,148 @@
+mod lin_master;
+mod lin_slave;
+
+pub use lin_master::LinMaster;
+pub use lin_master::LinSchedule;
+pub use lin_slave::LinSlave;
+
+use escw_mcu::common::Error;
+use escw_mcu::common::Result;
+
+use crate::hal::uart::*;
+use crate::peripheral::uart::Uart;
+
+/// The largest frame on the bus: break, sync, protected identifier, 8 data bytes and checksum.
+pub const LIN_FRAME_SIZE: usize = 12;
+
+pub const LIN_DATA_SIZE: usize = 8;
+
+pub const LIN_SYNC: u8 = 0x55;
+
+/// The timeout in milliseconds for a slave to send its response, 9 bytes take 5ms at 19200 bps.
+const LIN_RESPONSE_TIMEOUT: u32 = 10;
+
+/// The master request and slave response diagnostic frames always use the classic checksum.
+pub const LIN_MASTER_REQUEST_ID: u8 = 0x3C;
+pub const LIN_SLAVE_RESPONSE_ID: u8 = 0x3D;
+
+#[derive(Clone, Copy)]
+pub enum LinBreakLength
+{
+    Bits10,
+    Bits11,
+}
+
+impl Into<u32> for LinBreakLength
+{
+    fn into(self) -> u32
+    {
+        match self {
+            Self::Bits10 => UART_LINBREAKDETECTLENGTH_10B,
+            Self::Bits11 => UART_LINBREAKDETECTLENGTH_11B,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum LinChecksum
+{
+    /// LIN 1.x, over the data bytes only.
+    Classic,
+    /// LIN 2.x, over the protected identifier and the data bytes.
+    Enhanced,
+}
+
+/// The node which sends the response part of a frame, the header is always sent by the master.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum LinPublisher
+{
+    Master,
+    Slave,
+}
+
+/// One frame of a schedule table on the master, or one frame the slave answers or listens to.
+#[derive(Clone, Copy)]
+pub struct LinScheduleEntry
+{
+    pub id: u8,
+    pub publisher: LinPublisher,
+    pub size: usize,
+    pub checksum: LinChecksum,
+    /// The frame slot in milliseconds, only used by the master schedule.
+    pub slot: u32,
+}
+
+/// The application side of the frames, the master and the slave call it when a frame is due.
+pub trait LinFrameHandler
+{
+    /// Fill the response of a frame this node publishes.
+    fn publish(&mut self, id: u8, data: &mut [u8]);
+
+    /// Take the response of a frame this node subscribes to.
+    fn subscribe(&mut self, id: u8, data: LinResult<&[u8]>);
+}
+
+pub enum LinError
+{
+    /// The identifier is larger than 6 bits, or the data larger than 8 bytes.
+    Param,
+    /// The underlying UART refused or failed the transfer.
+    Uart(Error),
+    /// The sync byte or the parity of the protected identifier is wrong.
+    Header,
+    /// The response was received but its checksum does not match.
+    Checksum,
+}
+
+impl From<Error> for LinError
+{
+    fn from(value: Error) -> Self
+    {
+        LinError::Uart(value)
+    }
+}
+
+pub type LinResult<T> = core::result::Result<T, LinError>;
+
+/// Add the parity bits P0 and P1 to a 6 bits frame identifier.
+pub const fn protected_id(id: u8) -> u8
+{
+    let p0 = (id ^ id >> 1 ^ id >> 2 ^ id >> 4) & 0x01;
+    let p1 = !(id >> 1 ^ id >> 3 ^ id >> 4 ^ id >> 5) & 0x01;
+
+    (id & 0x3F) | p0 << 6 | p1 << 7
+}
+
+/// The inverted 8 bits sum with carry of the data, and of the protected identifier for the enhanced kind.
+pub fn checksum(kind: LinChecksum, pid: u8, data: &[u8]) -> u8
+{
+    let mut sum: u16 = match kind {
+        LinChecksum::Enhanced if pid & 0x3F != LIN_MASTER_REQUEST_ID && pid & 0x3F != LIN_SLAVE_RESPONSE_ID => pid as u16,
+        _ => 0,
+    };
+
+    for byte in data {
+        sum += *byte as u16;
+
+        if sum > 0xFF {
+            sum -= 0xFF;
+        }
+    }
+
+    !(sum as u8)
+}
+
+fn init(uart: &Uart, length: LinBreakLength) -> Result<()>
+{
+    unsafe { HAL_LIN_Init(uart.uart, length.into()).into() }
+}
+
+/// Drop the byte left in the data register, such as the echo of our own transmission.
+fn flush(uart: &Uart)
+{
+    unsafe {
+        let registers = (*uart.uart).instance as *const UartRegisters;
+        core::ptr::read_volatile(core::ptr::addr_of!((*registers).sr));
+        core::ptr::read_volatile(core::ptr::addr_of!((*registers).dr));
+    }
+}
@@ -0,0 +1,142 @@
+use escw_mcu::common::Result;
+use escw_mcu::peripheral::uart::UartDevice;
+
+use crate::hal::system::HAL_GetTick;
+use crate::hal::uart::*;
+use crate::peripheral::uart::Uart;
+
+use super::*;
+
+pub struct LinMaster
+{
+    uart: Uart,
+}
+
+impl LinMaster
+{
+    pub fn new(uart: Uart) -> Self
+    {
+        LinMaster { uart }
+    }
+
+    /// Switch the UART to LIN mode, not needed when the UART is already initialized as LIN by the HAL.
+    pub fn init(&self, length: LinBreakLength) -> Result<()>
+    {
+        init(&self.uart, length)
+    }
+
+    /// Send the break, the sync byte and the protected identifier of a frame.
+    pub fn send_header(&self, id: u8, timeout: u32) -> LinResult<()>
+    {
+        if id > 0x3F {
+            return Err(LinError::Param);
+        }
+
+        unsafe { HAL_LIN_SendBreak(self.uart.uart).ok()? };
+
+        Ok(self.uart.transmit(&[LIN_SYNC, protected_id(id)], timeout)?)
+    }
+
+    /// Run a frame whose response is published by the master.
+    pub fn write_frame(&self, id: u8, data: &[u8], kind: LinChecksum, timeout: u32) -> LinResult<()>
+    {
+        if data.is_empty() || data.len() > LIN_DATA_SIZE {
+            return Err(LinError::Param);
+        }
+
+        let mut response = [0u8; LIN_DATA_SIZE + 1];
+
+        response[..data.len()].copy_from_slice(data);
+        response[data.len()] = checksum(kind, protected_id(id), data);
+
+        self.send_header(id, timeout)?;
+
+        Ok(self.uart.transmit(&response[..data.len() + 1], timeout)?)
+    }
+
+    /// Run a frame whose response is published by a slave.
+    pub fn read_frame(&self, id: u8, data: &mut [u8], kind: LinChecksum, timeout: u32) -> LinResult<()>
+    {
+        if data.is_empty() || data.len() > LIN_DATA_SIZE {
+            return Err(LinError::Param);
+        }
+
+        let mut response = [0u8; LIN_DATA_SIZE + 1];
+
+        self.send_header(id, timeout)?;
+
+        // The transceiver echoes the header back, it must not be taken as the response.
+        flush(&self.uart);
+
+        self.uart.receive_fixed(&mut response[..data.len() + 1], timeout)?;
+
+        if response[data.len()] != checksum(kind, protected_id(id), &response[..data.len()]) {
+            return Err(LinError::Checksum);
+        }
+
+        data.copy_from_slice(&response[..data.len()]);
+
+        Ok(())
+    }
+}
+
+/// A schedule table of the master, its frames are run one after the other, each one in its own slot.
+pub struct LinSchedule<'a>
+{
+    table: &'a [LinScheduleEntry],
+    index: usize,
+    slot_tick: Option<u32>,
+}
+
+impl<'a> LinSchedule<'a>
+{
+    pub const fn new(table: &'a [LinScheduleEntry]) -> Self
+    {
+        LinSchedule { table, index: 0, slot_tick: None }
+    }
+
+    /// Restart the table from its first frame on the next poll.
+    pub fn reset(&mut self)
+    {
+        self.index = 0;
+        self.slot_tick = None;
+    }
+
+    /// Run the next frame of the table once the slot of the current one has elapsed.
+    ///
+    /// The failure of a frame published by a slave goes to the handler, it does not stop the schedule.
+    pub fn poll<H: LinFrameHandler>(&mut self, master: &LinMaster, handler: &mut H) -> LinResult<()>
+    {
+        if self.table.is_empty() {
+            return Ok(());
+        }
+
+        let now = unsafe { HAL_GetTick() };
+
+        if let Some(tick) = self.slot_tick {
+            if now.wrapping_sub(tick) < self.table[self.index].slot {
+                return Ok(());
+            }
+
+            self.index = (self.index + 1) % self.table.len();
+        }
+
+        self.slot_tick = Some(now);
+
+        let entry = self.table[self.index];
+        let mut data = [0u8; LIN_DATA_SIZE];
+        let data = &mut data[..entry.size.min(LIN_DATA_SIZE)];
+
+        match entry.publisher {
+            LinPublisher::Master => {
+                handler.publish(entry.id, data);
+                master.write_frame(entry.id, data, entry.checksum, entry.slot)
+            }
+            LinPublisher::Slave => {
+                let result = master.read_frame(entry.id, data, entry.checksum, entry.slot);
+                handler.subscribe(entry.id, result.map(|_| &*data));
+                Ok(())
+            }
+        }
+    }
+}
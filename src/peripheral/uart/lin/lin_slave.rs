@@ -0,0 +1,191 @@
+use core::sync::atomic::AtomicBool;
+use core::sync::atomic::AtomicU32;
+use core::sync::atomic::Ordering;
+
+use escw_mcu::common::Result;
+use escw_mcu::peripheral::uart::UartDevice;
+
+use crate::hal::uart::*;
+use crate::peripheral::uart::Uart;
+use crate::peripheral::uart::UartEvent;
+
+use super::*;
+
+pub struct LinSlave<'a>
+{
+    uart: Uart,
+    frames: &'a [LinScheduleEntry],
+    rx: [u8; LIN_FRAME_SIZE],
+    rx_size: AtomicU32,
+    rx_ready: AtomicBool,
+    rx_break: AtomicBool,
+    rx_broken: AtomicBool,
+    pending: Option<(LinScheduleEntry, u8)>,
+    response: [u8; LIN_DATA_SIZE + 1],
+    response_size: usize,
+}
+
+impl<'a> LinSlave<'a>
+{
+    /// Create a slave which answers the frames it publishes in `frames` and takes the ones it subscribes to.
+    pub fn new(uart: Uart, frames: &'a [LinScheduleEntry]) -> Self
+    {
+        LinSlave {
+            uart,
+            frames,
+            rx: [0; LIN_FRAME_SIZE],
+            rx_size: AtomicU32::new(0),
+            rx_ready: AtomicBool::new(false),
+            rx_break: AtomicBool::new(false),
+            rx_broken: AtomicBool::new(false),
+            pending: None,
+            response: [0; LIN_DATA_SIZE + 1],
+            response_size: 0,
+        }
+    }
+
+    /// Switch the UART to LIN mode, not needed when the UART is already initialized as LIN by the HAL.
+    pub fn init(&self, length: LinBreakLength) -> Result<()>
+    {
+        init(&self.uart, length)
+    }
+
+    /// Start to wait for the next header, `poll` does it again after every frame.
+    ///
+    /// The interrupt kind is used because a break is a framing error, which aborts a DMA reception.
+    pub fn listen(&mut self) -> Result<()>
+    {
+        self.uart.receive_async_int(&mut self.rx)
+    }
+
+    /// Forward the UART events here from the handle given to `Uart::with_event`,
+    /// events of the other UART instances are ignored.
+    pub fn on_event(&self, uart: &mut UartHandle, event: UartEvent)
+    {
+        if !core::ptr::eq(uart, self.uart.uart) {
+            return;
+        }
+
+        match event {
+            UartEvent::RxCompleted(size) => {
+                self.rx_size.store(size, Ordering::Relaxed);
+                self.rx_ready.store(true, Ordering::Release);
+            }
+            UartEvent::Error(error) => {
+                // The break is received as a 0x00 with framing error, the reception goes on after it.
+                if error.framing() {
+                    self.rx_break.store(true, Ordering::Release);
+                }
+
+                if error.overrun() || error.dma() {
+                    self.rx_broken.store(true, Ordering::Release);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Handle the header received since the last call, if any, answer it or take its response.
+    pub fn poll<H: LinFrameHandler>(&mut self, handler: &mut H) -> LinResult<()>
+    {
+        // Unless the UART has restarted the reception by itself.
+        if self.rx_broken.swap(false, Ordering::Acquire) && !self.uart.is_receiving() {
+            self.pending = None;
+            self.listen()?;
+        }
+
+        if !self.rx_ready.swap(false, Ordering::Acquire) {
+            return Ok(());
+        }
+
+        let size = self.rx_size.load(Ordering::Relaxed) as usize;
+        let result = self.process(size, handler);
+
+        self.listen()?;
+
+        result
+    }
+
+    fn process<H: LinFrameHandler>(&mut self, size: usize, handler: &mut H) -> LinResult<()>
+    {
+        let received_break = self.rx_break.swap(false, Ordering::Acquire);
+
+        // The rest of a response which came after an idle line, unless the master has moved to the next frame.
+        if let Some((entry, pid)) = self.pending.take() {
+            if !received_break {
+                return self.subscribe(entry, pid, size, 0, handler);
+            }
+        }
+
+        if !received_break {
+            return Ok(());
+        }
+
+        let start = match self.rx[..size].windows(2).position(|bytes| bytes == [0x00, LIN_SYNC]) {
+            Some(start) => start + 2,
+            None => return Err(LinError::Header),
+        };
+
+        if start >= size {
+            return Err(LinError::Header);
+        }
+
+        let pid = self.rx[start];
+
+        if protected_id(pid) != pid {
+            return Err(LinError::Header);
+        }
+
+        let entry = match self.frames.iter().find(|entry| entry.id == pid & 0x3F) {
+            Some(entry) => *entry,
+            None => return Ok(()),
+        };
+
+        self.response_size = 0;
+
+        match entry.publisher {
+            LinPublisher::Slave => self.publish(entry, pid, handler),
+            LinPublisher::Master => self.subscribe(entry, pid, size, start + 1, handler),
+        }
+    }
+
+    fn publish<H: LinFrameHandler>(&mut self, entry: LinScheduleEntry, pid: u8, handler: &mut H) -> LinResult<()>
+    {
+        let size = entry.size.min(LIN_DATA_SIZE);
+
+        handler.publish(entry.id, &mut self.response[..size]);
+        self.response[size] = checksum(entry.checksum, pid, &self.response[..size]);
+
+        self.uart.transmit(&self.response[..size + 1], LIN_RESPONSE_TIMEOUT)?;
+
+        // The transceiver echoes the response back, it must not be taken as the next header.
+        flush(&self.uart);
+
+        Ok(())
+    }
+
+    fn subscribe<H: LinFrameHandler>(&mut self, entry: LinScheduleEntry, pid: u8, size: usize, start: usize, handler: &mut H) -> LinResult<()>
+    {
+        let expected = entry.size.min(LIN_DATA_SIZE) + 1;
+        let count = (size - start).min(expected - self.response_size);
+
+        self.response[self.response_size..self.response_size + count].copy_from_slice(&self.rx[start..start + count]);
+        self.response_size += count;
+
+        if self.response_size < expected {
+            self.pending = Some((entry, pid));
+            return Ok(());
+        }
+
+        let data = &self.response[..expected - 1];
+
+        if self.response[expected - 1] != checksum(entry.checksum, pid, data) {
+            handler.subscribe(entry.id, Err(LinError::Checksum));
+        }
+        else {
+            handler.subscribe(entry.id, Ok(data));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,241 @@
+mod modbus_master;
+mod modbus_slave;
+
+pub use modbus_master::ModbusMaster;
+pub use modbus_slave::ModbusRegisterMap;
+pub use modbus_slave::ModbusSlave;
+
+use escw_mcu::common::Error;
+
+/// The maximum size of a RTU frame, address and CRC included.
+pub const MODBUS_ADU_SIZE: usize = 256;
+
+pub const MODBUS_BROADCAST: u8 = 0;
+
+const MODBUS_MAX_READ_BITS: u16 = 2000;
+const MODBUS_MAX_READ_REGISTERS: u16 = 125;
+const MODBUS_MAX_WRITE_BITS: u16 = 1968;
+const MODBUS_MAX_WRITE_REGISTERS: u16 = 123;
+
+#[repr(u8)]
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ModbusFunction
+{
+    ReadCoils = 0x01,
+    ReadDiscreteInputs = 0x02,
+    ReadHoldingRegisters = 0x03,
+    ReadInputRegisters = 0x04,
+    WriteSingleCoil = 0x05,
+    WriteSingleRegister = 0x06,
+    WriteMultipleCoils = 0x0F,
+    WriteMultipleRegisters = 0x10,
+}
+
+impl ModbusFunction
+{
+    /// The functions a master may broadcast, which change the slaves but have no response.
+    pub const fn is_write(&self) -> bool
+    {
+        matches!(
+            self,
+            Self::WriteSingleCoil | Self::WriteSingleRegister | Self::WriteMultipleCoils | Self::WriteMultipleRegisters
+        )
+    }
+}
+
+impl TryFrom<u8> for ModbusFunction
+{
+    type Error = ModbusException;
+
+    fn try_from(value: u8) -> core::result::Result<Self, Self::Error>
+    {
+        match value {
+            0x01 => Ok(Self::ReadCoils),
+            0x02 => Ok(Self::ReadDiscreteInputs),
+            0x03 => Ok(Self::ReadHoldingRegisters),
+            0x04 => Ok(Self::ReadInputRegisters),
+            0x05 => Ok(Self::WriteSingleCoil),
+            0x06 => Ok(Self::WriteSingleRegister),
+            0x0F => Ok(Self::WriteMultipleCoils),
+            0x10 => Ok(Self::WriteMultipleRegisters),
+            _ => Err(ModbusException::IllegalFunction),
+        }
+    }
+}
+
+#[repr(u8)]
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ModbusException
+{
+    IllegalFunction = 0x01,
+    IllegalDataAddress = 0x02,
+    IllegalDataValue = 0x03,
+    ServerDeviceFailure = 0x04,
+    Acknowledge = 0x05,
+    ServerDeviceBusy = 0x06,
+}
+
+impl TryFrom<u8> for ModbusException
+{
+    type Error = ModbusError;
+
+    fn try_from(value: u8) -> core::result::Result<Self, Self::Error>
+    {
+        match value {
+            0x01 => Ok(Self::IllegalFunction),
+            0x02 => Ok(Self::IllegalDataAddress),
+            0x03 => Ok(Self::IllegalDataValue),
+            0x04 => Ok(Self::ServerDeviceFailure),
+            0x05 => Ok(Self::Acknowledge),
+            0x06 => Ok(Self::ServerDeviceBusy),
+            _ => Err(ModbusError::Frame),
+        }
+    }
+}
+
+pub enum ModbusError
+{
+    /// The request does not fit in one frame or cannot be sent to this slave address.
+    Param,
+    /// The underlying UART refused or failed the transfer.
+    Uart(Error),
+    /// No response arrived before the timeout.
+    Timeout,
+    /// The frame was received but its CRC does not match.
+    Crc,
+    /// The frame is too short, comes from another slave or does not answer the request.
+    Frame,
+    /// The slave answered with an exception response.
+    Exception(ModbusException),
+}
+
+impl From<Error> for ModbusError
+{
+    fn from(value: Error) -> Self
+    {
+        ModbusError::Uart(value)
+    }
+}
+
+pub type ModbusResult<T> = core::result::Result<T, ModbusError>;
+
+/// The character and inter-frame silence times of the RTU line, derived from the baud rate.
+///
+/// Above 19200 bps the specification fixes t1.5 and t3.5 to 750us and 1750us.
+#[derive(Clone, Copy)]
+pub struct ModbusTiming
+{
+    char_us: u32,
+    t35_us: u32,
+}
+
+impl ModbusTiming
+{
+    /// None for a baud rate of 0.
+    pub const fn new(baud_rate: u32) -> Option<Self>
+    {
+        if baud_rate == 0 {
+            return None;
+        }
+
+        // One RTU character is 11 bits: start, 8 data, parity (or second stop) and stop.
+        let char_us = 11_000_000 / baud_rate;
+
+        if baud_rate > 19200 {
+            Some(ModbusTiming { char_us, t35_us: 1750 })
+        }
+        else {
+            Some(ModbusTiming { char_us, t35_us: char_us * 7 / 2 })
+        }
+    }
+
+    pub const fn char_us(&self) -> u32
+    {
+        self.char_us
+    }
+
+    pub const fn t35_us(&self) -> u32
+    {
+        self.t35_us
+    }
+
+    /// The t3.5 time in ticks of `HAL_GetTick`, rounded up with one more tick for the tick granularity.
+    const fn t35_ticks(&self) -> u32
+    {
+        self.t35_us.div_ceil(1000) + 1
+    }
+
+    /// The ticks a frame of `size` characters needs to be sent, plus the t3.5 silence ahead of it, rounded down.
+    const fn silence_ticks(&self, size: usize) -> u32
+    {
+        (self.char_us * size as u32 + self.t35_us) / 1000
+    }
+
+    /// A timeout in ticks large enough to send a frame of `size` characters.
+    const fn transmit_ticks(&self, size: usize) -> u32
+    {
+        (self.char_us * size as u32).div_ceil(1000) + self.t35_ticks()
+    }
+}
+
+/// CRC-16/MODBUS, polynomial 0xA001 (reflected 0x8005) with initial value 0xFFFF.
+pub fn crc16(data: &[u8]) -> u16
+{
+    let mut crc: u16 = 0xFFFF;
+
+    for byte in data {
+        crc ^= *byte as u16;
+
+        for _ in 0..8 {
+            if crc & 0x0001 != 0 {
+                crc = (crc >> 1) ^ 0xA001;
+            }
+            else {
+                crc >>= 1;
+            }
+        }
+    }
+
+    crc
+}
+
+/// Append the CRC of `frame[..size]` behind it, low byte first, and return the new frame size.
+fn append_crc(frame: &mut [u8], size: usize) -> usize
+{
+    let crc = crc16(&frame[..size]);
+    frame[size] = crc as u8;
+    frame[size + 1] = (crc >> 8) as u8;
+    size + 2
+}
+
+/// Check the trailing CRC of a whole frame and return the size of the frame without it.
+fn check_crc(frame: &[u8]) -> ModbusResult<usize>
+{
+    if frame.len() < 4 {
+        return Err(ModbusError::Frame);
+    }
+
+    let size = frame.len() - 2;
+
+    if crc16(&frame[..size]) != (frame[size] as u16 | (frame[size + 1] as u16) << 8) {
+        return Err(ModbusError::Crc);
+    }
+
+    Ok(size)
+}
+
+fn get_u16(frame: &[u8], at: usize) -> u16
+{
+    (frame[at] as u16) << 8 | frame[at + 1] as u16
+}
+
+fn put_u16(frame: &mut [u8], at: usize, value: u16)
+{
+    frame[at] = (value >> 8) as u8;
+    frame[at + 1] = value as u8;
+}
+
+fn bit_bytes(count: u16) -> usize
+{
+    (count as usize).div_ceil(8)
+}
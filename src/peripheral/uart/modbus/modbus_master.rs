@@ -0,0 +1,254 @@
+use core::sync::atomic::AtomicBool;
+use core::sync::atomic::AtomicU32;
+use core::sync::atomic::Ordering;
+
+use escw_mcu::peripheral::uart::UartDevice;
+
+use crate::hal::system::HAL_GetTick;
+use crate::hal::uart::UartHandle;
+use crate::peripheral::uart::Uart;
+use crate::peripheral::uart::UartEvent;
+
+use super::*;
+
+pub struct ModbusMaster
+{
+    uart: Uart,
+    timing: ModbusTiming,
+    tx: [u8; MODBUS_ADU_SIZE],
+    rx: [u8; MODBUS_ADU_SIZE],
+    rx_size: AtomicU32,
+    rx_ready: AtomicBool,
+    idle_tick: AtomicU32,
+}
+
+impl ModbusMaster
+{
+    /// A master on `uart` running at `baud_rate`, which must not be 0.
+    pub fn new(uart: Uart, baud_rate: u32) -> ModbusResult<Self>
+    {
+        Ok(ModbusMaster {
+            uart,
+            timing: ModbusTiming::new(baud_rate).ok_or(ModbusError::Param)?,
+            tx: [0; MODBUS_ADU_SIZE],
+            rx: [0; MODBUS_ADU_SIZE],
+            rx_size: AtomicU32::new(0),
+            rx_ready: AtomicBool::new(false),
+            idle_tick: AtomicU32::new(unsafe { HAL_GetTick() }),
+        })
+    }
+
+    /// Forward the UART events here from the handle given to `Uart::with_event`,
+    /// events of the other UART instances are ignored.
+    pub fn on_event(&self, uart: &mut UartHandle, event: UartEvent)
+    {
+        if !core::ptr::eq(uart, self.uart.uart) {
+            return;
+        }
+
+        if let UartEvent::RxCompleted(size) = event {
+            self.idle_tick.store(unsafe { HAL_GetTick() }, Ordering::Relaxed);
+            self.rx_size.store(size, Ordering::Relaxed);
+            self.rx_ready.store(true, Ordering::Release);
+        }
+    }
+
+    pub fn read_coils(&mut self, slave: u8, address: u16, coils: &mut [bool], timeout: u32) -> ModbusResult<()>
+    {
+        self.read_bits(ModbusFunction::ReadCoils, slave, address, coils, timeout)
+    }
+
+    pub fn read_discrete_inputs(&mut self, slave: u8, address: u16, inputs: &mut [bool], timeout: u32) -> ModbusResult<()>
+    {
+        self.read_bits(ModbusFunction::ReadDiscreteInputs, slave, address, inputs, timeout)
+    }
+
+    pub fn read_holding_registers(&mut self, slave: u8, address: u16, registers: &mut [u16], timeout: u32) -> ModbusResult<()>
+    {
+        self.read_registers(ModbusFunction::ReadHoldingRegisters, slave, address, registers, timeout)
+    }
+
+    pub fn read_input_registers(&mut self, slave: u8, address: u16, registers: &mut [u16], timeout: u32) -> ModbusResult<()>
+    {
+        self.read_registers(ModbusFunction::ReadInputRegisters, slave, address, registers, timeout)
+    }
+
+    pub fn write_single_coil(&mut self, slave: u8, address: u16, value: bool, timeout: u32) -> ModbusResult<()>
+    {
+        self.write_single(ModbusFunction::WriteSingleCoil, slave, address, if value { 0xFF00 } else { 0x0000 }, timeout)
+    }
+
+    pub fn write_single_register(&mut self, slave: u8, address: u16, value: u16, timeout: u32) -> ModbusResult<()>
+    {
+        self.write_single(ModbusFunction::WriteSingleRegister, slave, address, value, timeout)
+    }
+
+    pub fn write_multiple_coils(&mut self, slave: u8, address: u16, coils: &[bool], timeout: u32) -> ModbusResult<()>
+    {
+        if coils.is_empty() || coils.len() > MODBUS_MAX_WRITE_BITS as usize {
+            return Err(ModbusError::Param);
+        }
+
+        let bytes = bit_bytes(coils.len() as u16);
+
+        self.request(ModbusFunction::WriteMultipleCoils, slave, address, coils.len() as u16);
+        self.tx[6] = bytes as u8;
+        self.tx[7..7 + bytes].fill(0);
+
+        for (idx, coil) in coils.iter().enumerate() {
+            if *coil {
+                self.tx[7 + idx / 8] |= 1 << (idx % 8);
+            }
+        }
+
+        self.write_multiple(slave, 7 + bytes, timeout)
+    }
+
+    pub fn write_multiple_registers(&mut self, slave: u8, address: u16, registers: &[u16], timeout: u32) -> ModbusResult<()>
+    {
+        if registers.is_empty() || registers.len() > MODBUS_MAX_WRITE_REGISTERS as usize {
+            return Err(ModbusError::Param);
+        }
+
+        self.request(ModbusFunction::WriteMultipleRegisters, slave, address, registers.len() as u16);
+        self.tx[6] = (registers.len() * 2) as u8;
+
+        for (idx, register) in registers.iter().enumerate() {
+            put_u16(&mut self.tx, 7 + idx * 2, *register);
+        }
+
+        self.write_multiple(slave, 7 + registers.len() * 2, timeout)
+    }
+
+    fn read_bits(&mut self, function: ModbusFunction, slave: u8, address: u16, bits: &mut [bool], timeout: u32) -> ModbusResult<()>
+    {
+        if slave == MODBUS_BROADCAST || bits.is_empty() || bits.len() > MODBUS_MAX_READ_BITS as usize {
+            return Err(ModbusError::Param);
+        }
+
+        let bytes = bit_bytes(bits.len() as u16);
+
+        self.request(function, slave, address, bits.len() as u16);
+
+        if self.transact(slave, 6, timeout)? != 3 + bytes || self.rx[2] as usize != bytes {
+            return Err(ModbusError::Frame);
+        }
+
+        for (idx, bit) in bits.iter_mut().enumerate() {
+            *bit = self.rx[3 + idx / 8] & (1 << (idx % 8)) != 0;
+        }
+
+        Ok(())
+    }
+
+    fn read_registers(&mut self, function: ModbusFunction, slave: u8, address: u16, registers: &mut [u16], timeout: u32) -> ModbusResult<()>
+    {
+        if slave == MODBUS_BROADCAST || registers.is_empty() || registers.len() > MODBUS_MAX_READ_REGISTERS as usize {
+            return Err(ModbusError::Param);
+        }
+
+        self.request(function, slave, address, registers.len() as u16);
+
+        if self.transact(slave, 6, timeout)? != 3 + registers.len() * 2 || self.rx[2] as usize != registers.len() * 2 {
+            return Err(ModbusError::Frame);
+        }
+
+        for (idx, register) in registers.iter_mut().enumerate() {
+            *register = get_u16(&self.rx, 3 + idx * 2);
+        }
+
+        Ok(())
+    }
+
+    fn write_single(&mut self, function: ModbusFunction, slave: u8, address: u16, value: u16, timeout: u32) -> ModbusResult<()>
+    {
+        self.request(function, slave, address, value);
+
+        let size = self.transact(slave, 6, timeout)?;
+
+        // The normal response is an echo of the request.
+        if slave != MODBUS_BROADCAST && (size != 6 || self.rx[..6] != self.tx[..6]) {
+            return Err(ModbusError::Frame);
+        }
+
+        Ok(())
+    }
+
+    fn write_multiple(&mut self, slave: u8, size: usize, timeout: u32) -> ModbusResult<()>
+    {
+        let size = self.transact(slave, size, timeout)?;
+
+        // The normal response echoes the starting address and the quantity.
+        if slave != MODBUS_BROADCAST && (size != 6 || self.rx[2..6] != self.tx[2..6]) {
+            return Err(ModbusError::Frame);
+        }
+
+        Ok(())
+    }
+
+    fn request(&mut self, function: ModbusFunction, slave: u8, address: u16, value: u16)
+    {
+        self.tx[0] = slave;
+        self.tx[1] = function as u8;
+        put_u16(&mut self.tx, 2, address);
+        put_u16(&mut self.tx, 4, value);
+    }
+
+    /// Send the request built in `tx[..size]` and wait for the response of the slave.
+    ///
+    /// Return the size of the response without CRC, or 0 for a broadcast which gets no response.
+    fn transact(&mut self, slave: u8, size: usize, timeout: u32) -> ModbusResult<usize>
+    {
+        let size = append_crc(&mut self.tx, size);
+
+        self.wait_silence();
+        self.rx_ready.store(false, Ordering::Relaxed);
+
+        if slave != MODBUS_BROADCAST {
+            self.uart.receive_async_dma(&mut self.rx)?;
+        }
+
+        if let Err(error) = self.uart.transmit(&self.tx[..size], self.timing.transmit_ticks(size)) {
+            let _ = self.uart.abort_receive();
+            return Err(error.into());
+        }
+
+        let start = unsafe { HAL_GetTick() };
+        self.idle_tick.store(start, Ordering::Relaxed);
+
+        if slave == MODBUS_BROADCAST {
+            return Ok(0);
+        }
+
+        while !self.rx_ready.load(Ordering::Acquire) {
+            if unsafe { HAL_GetTick() }.wrapping_sub(start) >= timeout {
+                self.uart.abort_receive()?;
+                return Err(ModbusError::Timeout);
+            }
+        }
+
+        let size = check_crc(&self.rx[..self.rx_size.load(Ordering::Relaxed) as usize])?;
+
+        if self.rx[0] != slave {
+            return Err(ModbusError::Frame);
+        }
+
+        if self.rx[1] == self.tx[1] | 0x80 && size == 3 {
+            return Err(ModbusError::Exception(ModbusException::try_from(self.rx[2])?));
+        }
+
+        if self.rx[1] != self.tx[1] {
+            return Err(ModbusError::Frame);
+        }
+
+        Ok(size)
+    }
+
+    /// Keep the line silent for t3.5 since the last frame seen on it.
+    fn wait_silence(&self)
+    {
+        let idle = self.idle_tick.load(Ordering::Relaxed);
+
+        while unsafe { HAL_GetTick() }.wrapping_sub(idle) < self.timing.t35_ticks() {}
+    }
+}
@@ -0,0 +1,317 @@
+use core::sync::atomic::AtomicBool;
+use core::sync::atomic::AtomicU32;
+use core::sync::atomic::Ordering;
+
+use escw_mcu::common::Result;
+use escw_mcu::peripheral::uart::UartDevice;
+
+use crate::hal::system::HAL_GetTick;
+use crate::hal::uart::UartHandle;
+use crate::peripheral::uart::Uart;
+use crate::peripheral::uart::UartEvent;
+
+use super::*;
+
+/// The data model of a slave, every address not implemented answers `IllegalDataAddress`.
+pub trait ModbusRegisterMap
+{
+    fn read_coil(&mut self, _address: u16) -> core::result::Result<bool, ModbusException>
+    {
+        Err(ModbusException::IllegalDataAddress)
+    }
+
+    fn write_coil(&mut self, _address: u16, _value: bool) -> core::result::Result<(), ModbusException>
+    {
+        Err(ModbusException::IllegalDataAddress)
+    }
+
+    fn read_discrete_input(&mut self, _address: u16) -> core::result::Result<bool, ModbusException>
+    {
+        Err(ModbusException::IllegalDataAddress)
+    }
+
+    fn read_input_register(&mut self, _address: u16) -> core::result::Result<u16, ModbusException>
+    {
+        Err(ModbusException::IllegalDataAddress)
+    }
+
+    fn read_holding_register(&mut self, _address: u16) -> core::result::Result<u16, ModbusException>
+    {
+        Err(ModbusException::IllegalDataAddress)
+    }
+
+    fn write_holding_register(&mut self, _address: u16, _value: u16) -> core::result::Result<(), ModbusException>
+    {
+        Err(ModbusException::IllegalDataAddress)
+    }
+}
+
+pub struct ModbusSlave<M: ModbusRegisterMap>
+{
+    uart: Uart,
+    address: u8,
+    map: M,
+    timing: ModbusTiming,
+    tx: [u8; MODBUS_ADU_SIZE],
+    rx: [u8; MODBUS_ADU_SIZE],
+    rx_size: AtomicU32,
+    rx_tick: AtomicU32,
+    rx_ready: AtomicBool,
+    rx_broken: AtomicBool,
+    frame_tick: Option<u32>,
+}
+
+impl<M: ModbusRegisterMap> ModbusSlave<M>
+{
+    /// A slave on `uart` running at `baud_rate`, which must not be 0.
+    pub fn new(uart: Uart, address: u8, baud_rate: u32, map: M) -> ModbusResult<Self>
+    {
+        Ok(ModbusSlave {
+            uart,
+            address,
+            map,
+            timing: ModbusTiming::new(baud_rate).ok_or(ModbusError::Param)?,
+            tx: [0; MODBUS_ADU_SIZE],
+            rx: [0; MODBUS_ADU_SIZE],
+            rx_size: AtomicU32::new(0),
+            rx_tick: AtomicU32::new(0),
+            rx_ready: AtomicBool::new(false),
+            rx_broken: AtomicBool::new(false),
+            frame_tick: None,
+        })
+    }
+
+    pub fn map(&self) -> &M
+    {
+        &self.map
+    }
+
+    pub fn map_mut(&mut self) -> &mut M
+    {
+        &mut self.map
+    }
+
+    /// Start to wait for the next request, `poll` does it again after every request.
+    pub fn listen(&mut self) -> Result<()>
+    {
+        self.uart.receive_async_dma(&mut self.rx)
+    }
+
+    /// Forward the UART events here from the handle given to `Uart::with_event`,
+    /// events of the other UART instances are ignored.
+    pub fn on_event(&self, uart: &mut UartHandle, event: UartEvent)
+    {
+        if !core::ptr::eq(uart, self.uart.uart) {
+            return;
+        }
+
+        match event {
+            UartEvent::RxCompleted(size) => {
+                self.rx_tick.store(unsafe { HAL_GetTick() }, Ordering::Relaxed);
+                self.rx_size.store(size, Ordering::Relaxed);
+                self.rx_ready.store(true, Ordering::Release);
+            }
+            UartEvent::Error(_) => {
+                self.rx_broken.store(true, Ordering::Release);
+            }
+            _ => {}
+        }
+    }
+
+    /// Handle the request received since the last call, if any, and answer it.
+    ///
+    /// Call it from the main loop, never from the UART interrupt because the answer waits for t3.5.
+    pub fn poll(&mut self) -> ModbusResult<()>
+    {
+        // Unless the UART has restarted the reception by itself.
+        if self.rx_broken.swap(false, Ordering::Acquire) && !self.uart.is_receiving() {
+            self.listen()?;
+        }
+
+        if !self.rx_ready.swap(false, Ordering::Acquire) {
+            return Ok(());
+        }
+
+        let size = self.rx_size.load(Ordering::Relaxed) as usize;
+        let tick = self.rx_tick.load(Ordering::Relaxed);
+
+        // A frame which follows the previous one without t3.5 of silence is not a frame at all.
+        let separated = match self.frame_tick {
+            Some(last) => tick.wrapping_sub(last) >= self.timing.silence_ticks(size),
+            None => true,
+        };
+
+        self.frame_tick = Some(tick);
+
+        let result = if separated { self.process(size, tick) } else { Err(ModbusError::Frame) };
+
+        self.listen()?;
+
+        result
+    }
+
+    fn process(&mut self, size: usize, tick: u32) -> ModbusResult<()>
+    {
+        let size = check_crc(&self.rx[..size])?;
+
+        if self.rx[0] != self.address && self.rx[0] != MODBUS_BROADCAST {
+            return Ok(());
+        }
+
+        // Only the writes may be broadcast, the other requests are dropped.
+        if self.rx[0] == MODBUS_BROADCAST && !ModbusFunction::try_from(self.rx[1]).is_ok_and(|function| function.is_write()) {
+            return Ok(());
+        }
+
+        let response = match execute(&mut self.map, &self.rx[..size], &mut self.tx) {
+            Ok(response) => response,
+            Err(exception) => {
+                self.tx[1] = self.rx[1] | 0x80;
+                self.tx[2] = exception as u8;
+                3
+            }
+        };
+
+        if self.rx[0] == MODBUS_BROADCAST {
+            return Ok(());
+        }
+
+        self.tx[0] = self.address;
+
+        let response = append_crc(&mut self.tx, response);
+
+        while unsafe { HAL_GetTick() }.wrapping_sub(tick) < self.timing.t35_ticks() {}
+
+        self.uart.transmit(&self.tx[..response], self.timing.transmit_ticks(response))?;
+        self.frame_tick = Some(unsafe { HAL_GetTick() });
+
+        Ok(())
+    }
+}
+
+/// Execute the request on the map and build the normal response, return its size without CRC.
+fn execute<M: ModbusRegisterMap>(map: &mut M, request: &[u8], response: &mut [u8]) -> core::result::Result<usize, ModbusException>
+{
+    let function = ModbusFunction::try_from(request[1])?;
+
+    response[1] = request[1];
+
+    if request.len() < 6 {
+        return Err(ModbusException::IllegalDataValue);
+    }
+
+    let address = get_u16(request, 2);
+    let value = get_u16(request, 4);
+
+    match function {
+        ModbusFunction::ReadCoils | ModbusFunction::ReadDiscreteInputs => {
+            if request.len() != 6 || value == 0 || value > MODBUS_MAX_READ_BITS {
+                return Err(ModbusException::IllegalDataValue);
+            }
+
+            if address as u32 + value as u32 > 0x1_0000 {
+                return Err(ModbusException::IllegalDataAddress);
+            }
+
+            let bytes = bit_bytes(value);
+
+            response[2] = bytes as u8;
+            response[3..3 + bytes].fill(0);
+
+            for idx in 0..value as usize {
+                let bit = match function {
+                    ModbusFunction::ReadCoils => map.read_coil(address + idx as u16)?,
+                    _ => map.read_discrete_input(address + idx as u16)?,
+                };
+
+                if bit {
+                    response[3 + idx / 8] |= 1 << (idx % 8);
+                }
+            }
+
+            Ok(3 + bytes)
+        }
+        ModbusFunction::ReadHoldingRegisters | ModbusFunction::ReadInputRegisters => {
+            if request.len() != 6 || value == 0 || value > MODBUS_MAX_READ_REGISTERS {
+                return Err(ModbusException::IllegalDataValue);
+            }
+
+            if address as u32 + value as u32 > 0x1_0000 {
+                return Err(ModbusException::IllegalDataAddress);
+            }
+
+            response[2] = (value * 2) as u8;
+
+            for idx in 0..value as usize {
+                let register = match function {
+                    ModbusFunction::ReadHoldingRegisters => map.read_holding_register(address + idx as u16)?,
+                    _ => map.read_input_register(address + idx as u16)?,
+                };
+
+                put_u16(response, 3 + idx * 2, register);
+            }
+
+            Ok(3 + value as usize * 2)
+        }
+        ModbusFunction::WriteSingleCoil => {
+            let coil = match value {
+                0xFF00 if request.len() == 6 => true,
+                0x0000 if request.len() == 6 => false,
+                _ => return Err(ModbusException::IllegalDataValue),
+            };
+
+            map.write_coil(address, coil)?;
+            response[2..6].copy_from_slice(&request[2..6]);
+
+            Ok(6)
+        }
+        ModbusFunction::WriteSingleRegister => {
+            if request.len() != 6 {
+                return Err(ModbusException::IllegalDataValue);
+            }
+
+            map.write_holding_register(address, value)?;
+            response[2..6].copy_from_slice(&request[2..6]);
+
+            Ok(6)
+        }
+        ModbusFunction::WriteMultipleCoils => {
+            let bytes = bit_bytes(value);
+
+            if value == 0 || value > MODBUS_MAX_WRITE_BITS || request.len() != 7 + bytes || request[6] as usize != bytes {
+                return Err(ModbusException::IllegalDataValue);
+            }
+
+            if address as u32 + value as u32 > 0x1_0000 {
+                return Err(ModbusException::IllegalDataAddress);
+            }
+
+            for idx in 0..value as usize {
+                map.write_coil(address + idx as u16, request[7 + idx / 8] & (1 << (idx % 8)) != 0)?;
+            }
+
+            response[2..6].copy_from_slice(&request[2..6]);
+
+            Ok(6)
+        }
+        ModbusFunction::WriteMultipleRegisters => {
+            let bytes = value as usize * 2;
+
+            if value == 0 || value > MODBUS_MAX_WRITE_REGISTERS || request.len() != 7 + bytes || request[6] as usize != bytes {
+                return Err(ModbusException::IllegalDataValue);
+            }
+
+            if address as u32 + value as u32 > 0x1_0000 {
+                return Err(ModbusException::IllegalDataAddress);
+            }
+
+            for idx in 0..value as usize {
+                map.write_holding_register(address + idx as u16, get_u16(request, 7 + idx * 2))?;
+            }
+
+            response[2..6].copy_from_slice(&request[2..6]);
+
+            Ok(6)
+        }
+    }
+}
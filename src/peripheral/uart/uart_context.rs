@@ -0,0 +1,164 @@
+use crate::hal::uart::*;
+use crate::hal::HalStatus;
+use crate::peripheral::chunk::Chunks;
+use crate::peripheral::chunk::CHUNK_SIZE;
+
+use super::UartError;
+
+/// The UART instances of the largest F4 parts: USART1/2/3/6 and UART4/5/7/8.
+const UART_COUNT: usize = 8;
+
+static mut CONTEXT_CENTER: ContextCenter = ContextCenter::new();
+
+/// The asynchronous reception started last on an UART, which the callbacks need to know.
+///
+/// A reception longer than the HAL takes at once is received as consecutive chunks of the buffer.
+#[derive(Clone, Copy)]
+pub enum UartReception
+{
+    ToIdleInterrupt(*const u8, usize),
+    ToIdleDma(*const u8, usize),
+    Interrupt(*const u8, usize),
+    Dma(*const u8, usize),
+    CircularDma(*const u8, usize),
+}
+
+impl UartReception
+{
+    pub fn size(&self) -> usize
+    {
+        match self {
+            Self::ToIdleInterrupt(_, size) => *size,
+            Self::ToIdleDma(_, size) => *size,
+            Self::Interrupt(_, size) => *size,
+            Self::Dma(_, size) => *size,
+            Self::CircularDma(_, size) => *size,
+        }
+    }
+
+    pub fn is_long(&self) -> bool
+    {
+        self.size() > CHUNK_SIZE
+    }
+
+    /// The size of the chunk which starts at `offset` in the buffer.
+    pub fn chunk_size(&self, offset: usize) -> usize
+    {
+        (self.size() - offset).min(CHUNK_SIZE)
+    }
+
+    pub unsafe fn start(&self, uart: *mut UartHandle) -> HalStatus
+    {
+        self.start_at(uart, 0)
+    }
+
+    /// Start the chunk of the reception which begins at `offset` in the buffer.
+    pub unsafe fn start_at(&self, uart: *mut UartHandle, offset: usize) -> HalStatus
+    {
+        let size = self.chunk_size(offset) as u16;
+
+        match *self {
+            Self::ToIdleInterrupt(data, _) => HAL_UARTEx_ReceiveToIdle_IT(uart, data.add(offset), size),
+            Self::ToIdleDma(data, _) => HAL_UARTEx_ReceiveToIdle_DMA(uart, data.add(offset), size),
+            Self::Interrupt(data, _) => HAL_UART_Receive_IT(uart, data.add(offset), size),
+            Self::Dma(data, _) => HAL_UART_Receive_DMA(uart, data.add(offset), size),
+            Self::CircularDma(data, _) => HAL_UART_Receive_DMA(uart, data.add(offset), size),
+        }
+    }
+}
+
+/// An interrupt or DMA transmission too long for one call of the HAL, its chunks are started by the end events.
+#[derive(Clone, Copy)]
+pub struct UartTransmission
+{
+    pub dma: bool,
+    pub chunks: Chunks,
+}
+
+impl UartTransmission
+{
+    /// Start the next chunk, false when the transmission is done.
+    pub unsafe fn advance(&mut self, uart: *mut UartHandle) -> bool
+    {
+        if self.chunks.is_done() {
+            return false;
+        }
+
+        let chunk = self.chunks.next();
+
+        match self.dma {
+            true => HAL_UART_Transmit_DMA(uart, chunk.tx, chunk.size),
+            false => HAL_UART_Transmit_IT(uart, chunk.tx, chunk.size),
+        }
+        .ok()
+        .is_ok()
+    }
+}
+
+/// The number of each kind of error seen on an UART.
+#[derive(Clone, Copy, Default)]
+pub struct UartErrorCount
+{
+    pub parity: u32,
+    pub noise: u32,
+    pub framing: u32,
+    pub overrun: u32,
+    pub dma: u32,
+}
+
+impl UartErrorCount
+{
+    pub fn count(&mut self, error: UartError)
+    {
+        self.parity += error.parity() as u32;
+        self.noise += error.noise() as u32;
+        self.framing += error.framing() as u32;
+        self.overrun += error.overrun() as u32;
+        self.dma += error.dma() as u32;
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct UartContext
+{
+    pub reception: Option<UartReception>,
+    /// The bytes received by the chunks before the running one.
+    pub received: usize,
+    pub transmission: Option<UartTransmission>,
+    pub errors: UartErrorCount,
+    pub auto_restart: bool,
+}
+
+pub struct ContextCenter
+{
+    contexts: [Option<(*mut UartHandle, UartContext)>; UART_COUNT],
+}
+
+impl ContextCenter
+{
+    const fn new() -> Self
+    {
+        ContextCenter {
+            contexts: [None; UART_COUNT],
+        }
+    }
+
+    /// The context of an UART, created on first use, or `None` when all the slots are taken.
+    pub fn get(uart: *mut UartHandle) -> Option<&'static mut UartContext>
+    {
+        unsafe {
+            let contexts = &mut *core::ptr::addr_of_mut!(CONTEXT_CENTER.contexts);
+
+            let idx = match contexts.iter().position(|slot| matches!(slot, Some((handle, _)) if *handle == uart)) {
+                Some(idx) => idx,
+                None => {
+                    let idx = contexts.iter().position(|slot| slot.is_none())?;
+                    contexts[idx] = Some((uart, UartContext::default()));
+                    idx
+                }
+            };
+
+            contexts[idx].as_mut().map(|(_, context)| context)
+        }
+    }
+}
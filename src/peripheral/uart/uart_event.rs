@@ -1,11 +1,62 @@
+use crate::hal::uart::*;
+
+#[derive(Clone, Copy)]
 pub enum UartEvent
 {
     TxHalf,
     TxCompleted,
     TxAborted,
     RxHalf,
-    RxCompleted(u16),
+    RxCompleted(u32),
     RxAborted,
     TxRxAborted,
-    Error,
+    Error(UartError),
+}
+
+/// The error bits reported by the HAL when an error interrupt occurs.
+#[derive(Clone, Copy)]
+pub struct UartError(u32);
+
+impl UartError
+{
+    pub fn parity(&self) -> bool
+    {
+        self.0 & UART_ERROR_PE != 0
+    }
+
+    pub fn noise(&self) -> bool
+    {
+        self.0 & UART_ERROR_NE != 0
+    }
+
+    pub fn framing(&self) -> bool
+    {
+        self.0 & UART_ERROR_FE != 0
+    }
+
+    pub fn overrun(&self) -> bool
+    {
+        self.0 & UART_ERROR_ORE != 0
+    }
+
+    pub fn dma(&self) -> bool
+    {
+        self.0 & UART_ERROR_DMA != 0
+    }
+}
+
+impl From<u32> for UartError
+{
+    fn from(value: u32) -> Self
+    {
+        UartError(value)
+    }
+}
+
+impl Into<u32> for UartError
+{
+    fn into(self) -> u32
+    {
+        self.0
+    }
 }